@@ -0,0 +1,103 @@
+//! Latency and throughput of the park/unpark paths, for comparing backends:
+//!
+//!     cargo bench
+//!     cargo bench --features thread-backend
+
+use std::sync::atomic::{AtomicBool, Ordering::SeqCst};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// Unparking a parker that isn't parked, with and without a notification already pending
+fn uncontended_unpark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("uncontended_unpark");
+    group.bench_function("empty", |b| {
+        let (p, u) = parking::pair();
+        b.iter(|| {
+            u.unpark();
+            // Consume the notification so that every unpark finds the parker empty
+            p.park_timeout(Duration::from_millis(0));
+        })
+    });
+    group.bench_function("notified", |b| {
+        let (_p, u) = parking::pair();
+        u.unpark();
+        b.iter(|| u.unpark())
+    });
+    group.finish();
+}
+
+/// Round trips between two threads, each parking until the other unparks it
+fn ping_pong(c: &mut Criterion) {
+    c.bench_function("ping_pong", |b| {
+        b.iter_custom(|iters| {
+            let (p1, u1) = parking::pair();
+            let (p2, u2) = parking::pair();
+            let t = thread::spawn(move || {
+                for _ in 0..iters {
+                    p2.park();
+                    u1.unpark();
+                }
+            });
+            let start = Instant::now();
+            for _ in 0..iters {
+                u2.unpark();
+                p1.park();
+            }
+            let elapsed = start.elapsed();
+            t.join().unwrap();
+            elapsed
+        })
+    });
+}
+
+/// Overhead of a timed park that times out, against the time asked for
+fn timed_park(c: &mut Criterion) {
+    let mut group = c.benchmark_group("timed_park");
+    let p = parking::Parker::new();
+    for micros in [0u64, 1, 100] {
+        group.bench_with_input(BenchmarkId::from_parameter(micros), &micros, |b, &micros| {
+            b.iter(|| p.park_timeout(Duration::from_micros(micros)))
+        });
+    }
+    group.finish();
+}
+
+/// Notifications delivered to one parker by several unparking threads at once, one park per
+/// iteration
+fn producers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("producers");
+    group.throughput(Throughput::Elements(1));
+    for producers in [1usize, 2, 4, 8, 32] {
+        group.bench_with_input(BenchmarkId::from_parameter(producers), &producers, |b, &producers| {
+            b.iter_custom(|iters| {
+                let (p, u) = parking::pair();
+                let stop = Arc::new(AtomicBool::new(false));
+                let threads: Vec<_> = (0..producers).map(|_| {
+                    let (u, stop) = (u.clone(), stop.clone());
+                    thread::spawn(move || {
+                        while !stop.load(SeqCst) {
+                            u.unpark();
+                        }
+                    })
+                }).collect();
+                let start = Instant::now();
+                for _ in 0..iters {
+                    p.park();
+                }
+                let elapsed = start.elapsed();
+                stop.store(true, SeqCst);
+                for t in threads {
+                    t.join().unwrap();
+                }
+                elapsed
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, uncontended_unpark, ping_pong, timed_park, producers);
+criterion_main!(benches);
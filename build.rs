@@ -0,0 +1,58 @@
+//! Selects the blocking backend for the std parker, see `src/backend/mod.rs`
+
+use std::env;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rustc-check-cfg=cfg(parking_single_threaded)");
+    println!("cargo:rustc-check-cfg=cfg(parking_tsan)");
+    println!("cargo:rustc-check-cfg=cfg(parking_backend, values(\"condvar\", \"thread\", \"freertos\", \"futex\", \"lwp\", \"single\"))");
+    println!("cargo:rustc-check-cfg=cfg(parking_futex, values(\"custom\", \"fuchsia\", \"hermit\", \"linux\", \"openbsd\", \"redox\"))");
+
+    let feature = |name: &str| {
+        env::var_os(format!("CARGO_FEATURE_{}", name.to_uppercase().replace('-', "_"))).is_some()
+    };
+    let cfg = |name: &str| env::var(format!("CARGO_CFG_{}", name.to_uppercase())).unwrap_or_default();
+    let has = |name: &str, value: &str| cfg(name).split(',').any(|v| v == value);
+
+    // Without the atomics target feature, wasm has a single thread and nothing to wake it
+    let single_threaded = has("target_family", "wasm") && !has("target_feature", "atomics");
+    if single_threaded {
+        println!("cargo:rustc-cfg=parking_single_threaded");
+    }
+
+    // The annotations come from the TSan runtime, which is only linked in sanitized builds
+    if feature("tsan") && has("sanitize", "thread") {
+        println!("cargo:rustc-cfg=parking_tsan");
+    }
+
+    let target_os = cfg("target_os");
+    let native_futex = match target_os.as_str() {
+        "linux" | "android" => Some("linux"),
+        "fuchsia" => Some("fuchsia"),
+        "hermit" => Some("hermit"),
+        "openbsd" => Some("openbsd"),
+        "redox" => Some("redox"),
+        _ => None
+    };
+
+    let (backend, futex) = if feature("custom-backend") {
+        ("futex", Some("custom"))
+    } else if single_threaded {
+        ("single", None)
+    } else if feature("freertos-backend") && target_os == "espidf" {
+        ("freertos", None)
+    } else if feature("thread-backend") {
+        ("thread", None)
+    } else if native_futex.is_some() {
+        ("futex", native_futex)
+    } else if target_os == "netbsd" {
+        ("lwp", None)
+    } else {
+        ("condvar", None)
+    };
+    if let Some(futex) = futex {
+        println!("cargo:rustc-cfg=parking_futex=\"{}\"", futex);
+    }
+    println!("cargo:rustc-cfg=parking_backend=\"{}\"", backend);
+}
@@ -5,7 +5,7 @@ fn main() {
     let (p, u) = parking::pair();
 
     // Notify the parker
-    assert_eq!(u.unpark(), true);
+    assert!(u.unpark());
 
     // Wakes up immediately because the parker is notified
     p.park();
@@ -0,0 +1,60 @@
+//! Exercises park/unpark end to end and exits non-zero if anything is off. Meant to be run on
+//! targets without a test harness, e.g. under uhyve for Hermit:
+//!
+//!     cargo build --example park_unpark --target x86_64-unknown-hermit -Zbuild-std=std,panic_abort
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn main() {
+    let (p, u) = parking::pair();
+
+    // A notification is kept until the next park consumes it
+    assert!(u.unpark());
+    assert!(!u.unpark());
+    p.park();
+
+    // Nothing pending, so a timed park times out, and not early
+    let start = Instant::now();
+    assert!(!p.park_timeout(Duration::from_millis(50)));
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    // Zero and sub-microsecond timeouts return promptly
+    assert!(!p.park_timeout(Duration::from_millis(0)));
+    assert!(!p.park_timeout(Duration::from_nanos(1)));
+    assert!(!p.park_timeout(Duration::from_nanos(999)));
+
+    // The longest timeout doesn't overflow, it just waits until notified
+    let u2 = u.clone();
+    let t = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        u2.unpark();
+    });
+    assert!(p.park_timeout(Duration::new(u64::MAX, 999_999_999)));
+    t.join().unwrap();
+
+    // Wake a parked thread from another thread
+    let u2 = u.clone();
+    let t = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        u2.unpark();
+    });
+    assert!(p.park_timeout(Duration::from_secs(10)));
+    t.join().unwrap();
+
+    // Ping-pong between two parkers
+    let (p2, u2) = parking::pair();
+    let t = thread::spawn(move || {
+        for _ in 0..1000 {
+            p2.park();
+            u.unpark();
+        }
+    });
+    for _ in 0..1000 {
+        u2.unpark();
+        p.park();
+    }
+    t.join().unwrap();
+
+    println!("park_unpark: ok");
+}
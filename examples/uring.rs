@@ -0,0 +1,48 @@
+//! Blocks in one `submit_and_wait` on both a ring timeout, standing in for I/O, and a parker,
+//! and exits non-zero if the unpark doesn't end the wait. Needs Linux 6.7 or later:
+//!
+//!     cargo run --example uring --features io-uring
+//!
+//! The futex backend must be in use, so this does nothing along with `custom-backend`.
+
+#[cfg(all(parking_futex = "linux", target_os = "linux"))]
+fn main() {
+    use std::thread;
+    use std::time::Duration;
+
+    use io_uring::{opcode, types, IoUring};
+
+    const TIMEOUT: u64 = 1;
+    const UNPARKED: u64 = 2;
+
+    let (p, u) = parking::pair();
+    let mut ring = IoUring::new(8).unwrap();
+
+    // Start the unparking thread before submitting, see `UringWait::entry`
+    let t = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        u.unpark();
+    });
+
+    let wait = p.uring_wait().expect("no notification pending yet");
+    let timeout = types::Timespec::new().sec(10);
+    unsafe {
+        let mut sq = ring.submission();
+        sq.push(&opcode::Timeout::new(&timeout).build().user_data(TIMEOUT)).unwrap();
+        sq.push(&wait.entry().user_data(UNPARKED)).unwrap();
+    }
+    ring.submit().unwrap();
+
+    ring.submit_and_wait(1).unwrap();
+    let cqe = ring.completion().next().unwrap();
+    assert_eq!(cqe.user_data(), UNPARKED, "the unpark should come before the timeout");
+    assert!(wait.finish());
+    t.join().unwrap();
+
+    println!("uring: ok");
+}
+
+#[cfg(not(all(parking_futex = "linux", target_os = "linux")))]
+fn main() {
+    println!("uring: skipped, needs the futex backend on Linux");
+}
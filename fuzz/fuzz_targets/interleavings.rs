@@ -0,0 +1,145 @@
+//! Decodes the input into schedules for a parking thread and an unparking thread and checks the
+//! same invariants as `tests/state_machine.rs`:
+//!
+//!     cargo +nightly fuzz run interleavings
+//!
+//! Even bytes go to the parking thread and odd bytes to the unparking thread. The high bits pick
+//! the operation and the low bits its argument, so small mutations of the input shift timing
+//! around rather than rewriting the whole schedule.
+
+#![no_main]
+
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Clone, Copy)]
+enum ParkOp {
+    /// Park with a timeout of this many microseconds, zero never blocking
+    Timeout(u64),
+    /// Park forever, relying on the rescuer if nobody else unparks
+    Park,
+    Yield
+}
+
+#[derive(Debug, Clone, Copy)]
+enum UnparkOp {
+    Unpark,
+    /// Sleep this many microseconds
+    Sleep(u64),
+    Yield
+}
+
+fn park_op(byte: u8) -> ParkOp {
+    let arg = (byte & 0x3f) as u64;
+    match byte >> 6 {
+        0 => ParkOp::Timeout(0),
+        1 => ParkOp::Timeout(arg * 4),
+        2 => ParkOp::Park,
+        _ => ParkOp::Yield
+    }
+}
+
+fn unpark_op(byte: u8) -> UnparkOp {
+    match byte >> 6 {
+        0 | 1 => UnparkOp::Unpark,
+        2 => UnparkOp::Sleep((byte & 0x3f) as u64 * 4),
+        _ => UnparkOp::Yield
+    }
+}
+
+/// Runs the parking thread's schedule alone against a model of the pending notification, with
+/// the unparking thread's operations interleaved in input order
+fn sequential(data: &[u8]) {
+    let (p, u) = parking::pair();
+    let mut pending = false;
+
+    for (i, &byte) in data.iter().enumerate() {
+        if i % 2 == 1 {
+            if let UnparkOp::Unpark = unpark_op(byte) {
+                assert_eq!(u.unpark(), !pending, "unpark disagrees with the model");
+                pending = true;
+            }
+            continue;
+        }
+        match park_op(byte) {
+            // Park forever only when it can't hang
+            ParkOp::Park if pending => p.park(),
+            ParkOp::Park => assert!(!p.park_timeout(Duration::ZERO), "park woke without a notification"),
+            ParkOp::Timeout(micros) => {
+                assert_eq!(p.park_timeout(Duration::from_micros(micros)), pending, "park disagrees with the model");
+            }
+            ParkOp::Yield => continue
+        }
+        pending = false;
+    }
+
+    assert_eq!(p.park_timeout(Duration::ZERO), pending, "notification lost or invented");
+}
+
+/// Runs both schedules on their own threads, checking that every notification an unpark stored
+/// is consumed exactly once
+fn concurrent(data: &[u8]) {
+    let (p, u) = parking::pair();
+    let done = Arc::new(AtomicBool::new(false));
+
+    let parks: Vec<_> = data.iter().step_by(2).map(|&b| park_op(b)).collect();
+    let unparks: Vec<_> = data.iter().skip(1).step_by(2).map(|&b| unpark_op(b)).collect();
+
+    let unparker = {
+        let u = u.clone();
+        thread::spawn(move || {
+            let mut stored = 0;
+            for op in unparks {
+                match op {
+                    UnparkOp::Unpark => stored += u.unpark() as usize,
+                    UnparkOp::Sleep(micros) => thread::sleep(Duration::from_micros(micros)),
+                    UnparkOp::Yield => thread::yield_now()
+                }
+            }
+            stored
+        })
+    };
+
+    // Untimed parks may outnumber the notifications, so keep unparking until the parking
+    // thread is done
+    let rescuer = {
+        let done = done.clone();
+        thread::spawn(move || {
+            let mut stored = 0;
+            while !done.load(SeqCst) {
+                stored += u.unpark() as usize;
+                thread::sleep(Duration::from_millis(1));
+            }
+            stored
+        })
+    };
+
+    let mut consumed = 0;
+    for op in parks {
+        match op {
+            ParkOp::Timeout(micros) => consumed += p.park_timeout(Duration::from_micros(micros)) as usize,
+            ParkOp::Park => {
+                p.park();
+                consumed += 1;
+            }
+            ParkOp::Yield => thread::yield_now()
+        }
+    }
+    done.store(true, SeqCst);
+
+    let stored = unparker.join().unwrap() + rescuer.join().unwrap();
+    let left = p.park_timeout(Duration::ZERO) as usize;
+    assert_eq!(consumed + left, stored, "notification lost or invented");
+}
+
+fuzz_target!(|data: &[u8]| {
+    // Keep each run short, timeouts add up
+    let data = &data[..data.len().min(256)];
+    sequential(data);
+    concurrent(data);
+});
@@ -0,0 +1,18 @@
+//! Atomics behind the state word and its neighbours
+//!
+//! With the `portable-atomic` feature these come from the `portable-atomic` crate, so that targets
+//! without native compare-and-swap can still build. Enabling `critical-section` as well makes it
+//! fall back to critical sections there.
+
+#[cfg(not(feature = "portable-atomic"))]
+pub(crate) use core::sync::atomic::AtomicU32;
+#[cfg(all(feature = "std", not(feature = "portable-atomic")))]
+pub(crate) use core::sync::atomic::{AtomicBool, AtomicUsize};
+#[cfg(all(any(feature = "metrics", feature = "diagnostics"), not(feature = "portable-atomic")))]
+pub(crate) use core::sync::atomic::AtomicU64;
+#[cfg(feature = "portable-atomic")]
+pub(crate) use portable_atomic::AtomicU32;
+#[cfg(all(feature = "std", feature = "portable-atomic"))]
+pub(crate) use portable_atomic::{AtomicBool, AtomicUsize};
+#[cfg(all(any(feature = "metrics", feature = "diagnostics"), feature = "portable-atomic"))]
+pub(crate) use portable_atomic::AtomicU64;
@@ -0,0 +1,131 @@
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::Instant;
+
+use crate::atomic::AtomicU32;
+use crate::clock::Clock;
+use crate::watchdog::StallClock;
+use crate::parker::{Wakeup, EMPTY, NOTIFIED, PARKED};
+
+/// Blocks on a condition variable, with `lock` guarding the transition into `PARKED`
+pub(crate) struct Waiter {
+    lock: Mutex<()>,
+    cvar: Condvar
+}
+
+impl Waiter {
+
+    pub(crate) fn new() -> Waiter {
+        Waiter {
+            lock: Mutex::new(()),
+            cvar: Condvar::new()
+        }
+    }
+
+    pub(crate) fn park(&self, state: &AtomicU32, deadline: Option<Instant>, clock: Clock, stall: &mut Option<StallClock<'_>>) -> Wakeup {
+        // Otherwise we need to coordinate going to sleep
+        let mut m = self.lock.lock().unwrap();
+
+        match state.compare_exchange(EMPTY, PARKED, SeqCst, SeqCst) {
+            Ok(_) => {},
+            // Consume this notification to avoid spurious wakeups in the next park
+            Err(NOTIFIED) => {
+                let old = state.swap(EMPTY, SeqCst);
+                assert_eq!(old, NOTIFIED, "park state changed unexpectedly");
+                return Wakeup::notified(0);
+            }
+            Err(n) => panic!("inconsistent park_timeout state: {}", n)
+        }
+
+        match deadline {
+            None => {
+                let mut spurious = 0;
+                loop {
+                    // Block the current thread on the conditional variable
+                    m = self.wait(state, m, None, clock, stall);
+                    if state.compare_exchange(NOTIFIED, EMPTY, SeqCst, SeqCst).is_ok() {
+                        // got a notification
+                        return Wakeup::notified(spurious);
+                    }
+                    spurious += 1;
+                }
+            }
+            Some(deadline) => {
+                // Wakeups before the deadline without a notification are spurious, so keep
+                // waiting out whatever time remains
+                let mut spurious = 0;
+                loop {
+                    m = self.wait(state, m, Some(deadline), clock, stall);
+                    if state.compare_exchange(NOTIFIED, EMPTY, SeqCst, SeqCst).is_ok() {
+                        return Wakeup::notified(spurious);
+                    }
+                    if clock.reached(&mut clock.now(), deadline) {
+                        // A notification may have come in since the check above
+                        return match state.swap(EMPTY, SeqCst) {
+                            NOTIFIED => Wakeup::notified(spurious),
+                            PARKED => Wakeup::timed_out(spurious),
+                            n => panic!("inconsistent park_timeout state: {}", n)
+                        };
+                    }
+                    spurious += 1;
+                }
+            }
+        }
+    }
+
+    /// Waits on `cvar` once, or until `deadline` on `clock`, reporting to the watchdog each time
+    /// the park has been stalled for another threshold in between
+    ///
+    /// return the reacquired guard
+    fn wait<'a>(
+        &'a self,
+        state: &AtomicU32,
+        mut m: MutexGuard<'a, ()>,
+        deadline: Option<Instant>,
+        clock: Clock,
+        stall: &mut Option<StallClock<'_>>
+    ) -> MutexGuard<'a, ()> {
+        let stall = match stall {
+            Some(stall) => stall,
+            None => return match deadline {
+                None => self.cvar.wait(m).unwrap(),
+                Some(deadline) => self.cvar.wait_timeout(m, deadline.saturating_duration_since(clock.now())).unwrap().0
+            }
+        };
+
+        loop {
+            let (until, is_deadline) = match deadline {
+                Some(deadline) if deadline <= stall.next_report() => (deadline, true),
+                _ => (stall.next_report(), false)
+            };
+            let (guard, result) = self.cvar.wait_timeout(m, until.saturating_duration_since(clock.now())).unwrap();
+            m = guard;
+            if !result.timed_out() || is_deadline {
+                return m;
+            }
+
+            // Release `lock` while the callback runs so that it can't hold up unparkers. A
+            // notification sent in the meantime finds nobody waiting on `cvar`, so check `state`
+            // before going back to sleep
+            drop(m);
+            stall.report();
+            m = self.lock.lock().unwrap();
+            if state.load(SeqCst) == NOTIFIED {
+                return m;
+            }
+        }
+    }
+
+    pub(crate) fn unpark(&self, _state: &AtomicU32) {
+        // There is a period between when the parked thread sets `state` to `PARKED` (or last
+        // checked `state` in the case of a spurious wakeup) and when it actually waits on `cvar`.
+        // If we were to notify during this period it would be ignored and then when the parked
+        // thread went to sleep it would never wake up. Fortunately, it has `lock` locked at this
+        // stage so we can acquire `lock` to wait until it is ready to receive the notification.
+        //
+        // Releasing `lock` before the call to `notify_one` means that when the parked thread wakes
+        // it doesn't get woken only to have to wait for us to release `lock`.
+        drop(self.lock.lock().unwrap());
+        self.cvar.notify_one();
+    }
+}
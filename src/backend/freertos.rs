@@ -0,0 +1,90 @@
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::atomic::AtomicPtr;
+use std::time::{Duration, Instant};
+
+use crate::atomic::AtomicU32;
+use crate::clock::Clock;
+use crate::config::parse_rate;
+use crate::parker::Wakeup;
+use crate::watchdog::StallClock;
+
+type TaskHandle = *mut c_void;
+type BaseType = i32;
+type UBaseType = u32;
+type TickType = u32;
+
+/// `eIncrement` from `eNotifyAction`
+const E_INCREMENT: u32 = 2;
+const PD_TRUE: BaseType = 1;
+const PORT_MAX_DELAY: TickType = TickType::MAX;
+
+/// Notification index used for parking, the one `xTaskNotifyGive` and `ulTaskNotifyTake` use
+const NOTIFY_INDEX: UBaseType = 0;
+
+/// `configTICK_RATE_HZ` of the FreeRTOS build, which isn't visible from Rust. Set the
+/// `PARKING_FREERTOS_TICK_RATE_HZ` environment variable at build time if it isn't ESP-IDF's
+/// default of 100.
+const TICK_RATE_HZ: u64 = match option_env!("PARKING_FREERTOS_TICK_RATE_HZ") {
+    Some(hz) => parse_rate(hz),
+    None => 100
+};
+
+extern "C" {
+    fn xTaskGetCurrentTaskHandle() -> TaskHandle;
+    fn ulTaskGenericNotifyTake(index: UBaseType, clear_count_on_exit: BaseType, ticks_to_wait: TickType) -> u32;
+    fn xTaskGenericNotify(
+        task: TaskHandle,
+        index: UBaseType,
+        value: u32,
+        action: u32,
+        previous_value: *mut u32
+    ) -> BaseType;
+}
+
+/// Blocks on the parked task's direct-to-task notification
+pub(crate) struct Waiter {
+    /// The task currently in `park`, published before `state` becomes `PARKED`
+    task: AtomicPtr<c_void>
+}
+
+impl Waiter {
+
+    pub(crate) fn new() -> Waiter {
+        Waiter {
+            task: AtomicPtr::new(ptr::null_mut())
+        }
+    }
+
+    pub(crate) fn park(&self, state: &AtomicU32, deadline: Option<Instant>, clock: Clock, stall: &mut Option<StallClock<'_>>) -> Wakeup {
+        // A `Parker` is `Send`, so the task may differ from the last park
+        // SAFETY: always callable from a task
+        self.task.store(unsafe { xTaskGetCurrentTaskHandle() }, SeqCst);
+
+        super::sleep::park_with(state, deadline, clock, stall, |until, now| {
+            let ticks = match until {
+                None => PORT_MAX_DELAY,
+                Some(until) => to_ticks(until.saturating_duration_since(now))
+            };
+            // SAFETY: takes the calling task's own notification
+            unsafe { ulTaskGenericNotifyTake(NOTIFY_INDEX, PD_TRUE, ticks) };
+        })
+    }
+
+    pub(crate) fn unpark(&self, _state: &AtomicU32) {
+        // `park` publishes the task before `state` becomes `PARKED`, so it is always set here.
+        // Tasks run until deleted, and a task that parked and got deleted can't be unparked by
+        // anyone observing `PARKED` for it.
+        let task = self.task.load(SeqCst);
+        // SAFETY: `task` is the handle of a live task, see above
+        unsafe { xTaskGenericNotify(task, NOTIFY_INDEX, 0, E_INCREMENT, ptr::null_mut()) };
+    }
+}
+
+/// Rounds up so a timed park never returns before its timeout, capping just below
+/// `portMAX_DELAY`, which would mean waiting forever
+fn to_ticks(duration: Duration) -> TickType {
+    let ticks = (duration.as_nanos() * TICK_RATE_HZ as u128).div_ceil(1_000_000_000);
+    if ticks >= PORT_MAX_DELAY as u128 { PORT_MAX_DELAY - 1 } else { ticks as TickType }
+}
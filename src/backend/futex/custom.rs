@@ -0,0 +1,52 @@
+use std::sync::atomic::AtomicU32;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+static BACKEND: OnceLock<&'static dyn Backend> = OnceLock::new();
+
+/// Blocking primitive supplied by the application, for targets this crate can't block on by
+/// itself
+///
+/// Enabled by the `custom-backend` feature, which makes every parker block through the backend
+/// registered with `set_backend`. The interface is that of a futex.
+pub trait Backend: Sync {
+    /// Sleeps while `futex` holds `expected`, for at most `timeout`
+    ///
+    /// May return early, spuriously or because the value already differs.
+    fn wait(&self, futex: &AtomicU32, expected: u32, timeout: Option<Duration>);
+
+    /// Wakes a thread sleeping in `wait` on `futex`, if there is one
+    fn wake_one(&self, futex: &AtomicU32);
+}
+
+/// Registers the backend every parker blocks through
+///
+/// Must be called before anything parks. Returns `backend` back if one was already registered.
+pub fn set_backend(backend: &'static dyn Backend) -> Result<(), &'static dyn Backend> {
+    BACKEND.set(backend)
+}
+
+fn backend() -> &'static dyn Backend {
+    *BACKEND.get().expect("the `custom-backend` feature requires a backend registered with `parking::set_backend` before parking")
+}
+
+/// Return the state word as the std atomic the `Backend` interface takes
+fn as_std(futex: &crate::atomic::AtomicU32) -> &AtomicU32 {
+    #[cfg(feature = "portable-atomic")]
+    // SAFETY: the word is a valid, aligned `u32` for as long as `futex` is borrowed, and every
+    // access to it is atomic
+    return unsafe { AtomicU32::from_ptr(futex.as_ptr()) };
+    #[cfg(not(feature = "portable-atomic"))]
+    futex
+}
+
+pub(super) fn wait(futex: &crate::atomic::AtomicU32, expected: u32, until: Option<Instant>, now: Instant) {
+    backend().wait(as_std(futex), expected, until.map(|until| until.saturating_duration_since(now)))
+}
+
+pub(super) fn wake_one(futex: &crate::atomic::AtomicU32) {
+    // Nothing can be parked before a backend is registered
+    if let Some(backend) = BACKEND.get() {
+        backend.wake_one(as_std(futex))
+    }
+}
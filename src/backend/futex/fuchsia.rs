@@ -0,0 +1,41 @@
+use std::convert::TryFrom;
+use std::time::Instant;
+
+use crate::atomic::AtomicU32;
+
+type Status = i32;
+type Handle = u32;
+type Time = i64;
+
+const ZX_HANDLE_INVALID: Handle = 0;
+const ZX_TIME_INFINITE: Time = Time::MAX;
+
+#[link(name = "zircon")]
+extern "C" {
+    fn zx_futex_wait(value_ptr: *const AtomicU32, current_value: u32, new_futex_owner: Handle, deadline: Time) -> Status;
+    fn zx_futex_wake(value_ptr: *const AtomicU32, wake_count: u32) -> Status;
+    fn zx_clock_get_monotonic() -> Time;
+}
+
+/// Sleeps while `futex` holds `expected`, at most until `until`, which is `now` plus the
+/// timeout. Returns early, spuriously or because the value already differs, without saying
+/// which.
+pub(super) fn wait(futex: &AtomicU32, expected: u32, until: Option<Instant>, now: Instant) {
+    let deadline = match until {
+        None => ZX_TIME_INFINITE,
+        Some(until) => {
+            let timeout = until.saturating_duration_since(now);
+            let nanos = Time::try_from(timeout.as_nanos()).unwrap_or(Time::MAX);
+            // SAFETY: reads the monotonic clock
+            unsafe { zx_clock_get_monotonic() }.saturating_add(nanos)
+        }
+    };
+    // SAFETY: `futex` is a valid, aligned 32-bit word. Every outcome, whether woken, timed out
+    // or the value had already changed, sends the caller back to check `state`.
+    unsafe { zx_futex_wait(futex, expected, ZX_HANDLE_INVALID, deadline) };
+}
+
+pub(super) fn wake_one(futex: &AtomicU32) {
+    // SAFETY: `futex` is a valid, aligned 32-bit word
+    unsafe { zx_futex_wake(futex, 1) };
+}
@@ -0,0 +1,24 @@
+use std::time::Instant;
+
+use hermit_abi::{futex_wait, futex_wake, timespec, FUTEX_RELATIVE_TIMEOUT};
+
+use crate::atomic::AtomicU32;
+
+/// Sleeps while `futex` holds `expected`, at most until `until`, which is `now` plus the
+/// timeout. Returns early, spuriously or because the value already differs, without saying
+/// which.
+pub(super) fn wait(futex: &AtomicU32, expected: u32, until: Option<Instant>, now: Instant) {
+    let timeout = until.map(|until| until.saturating_duration_since(now)).map(|timeout| timespec {
+        tv_sec: timeout.as_secs().min(i64::MAX as u64) as i64,
+        tv_nsec: timeout.subsec_nanos() as i32
+    });
+    let timeout = timeout.as_ref().map_or(std::ptr::null(), |timeout| timeout as *const timespec);
+    // SAFETY: `futex` is a valid, aligned 32-bit word and `timeout` is null or points to a
+    // `timespec` that outlives the call. Every outcome sends the caller back to check `state`.
+    unsafe { futex_wait(futex.as_ptr(), expected, timeout, FUTEX_RELATIVE_TIMEOUT) };
+}
+
+pub(super) fn wake_one(futex: &AtomicU32) {
+    // SAFETY: `futex` is a valid, aligned 32-bit word
+    unsafe { futex_wake(futex.as_ptr(), 1) };
+}
@@ -0,0 +1,63 @@
+use std::convert::TryFrom;
+use std::ptr;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::atomic::AtomicU32;
+
+/// An `Instant` and the `CLOCK_MONOTONIC` reading taken alongside it, to translate deadlines
+/// from one to the other
+///
+/// std measures `Instant`s on `CLOCK_MONOTONIC` too, so the offset between the two never
+/// changes and one pair serves the whole process.
+static ANCHOR: OnceLock<(Instant, Duration)> = OnceLock::new();
+
+fn monotonic_now() -> Duration {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: `ts` is a valid `timespec` to write to. `CLOCK_MONOTONIC` is always supported.
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+}
+
+/// Return `until` as an absolute `CLOCK_MONOTONIC` time, or `None` if `timespec` can't hold it
+fn to_timespec(until: Instant) -> Option<libc::timespec> {
+    let &(instant, monotonic) = ANCHOR.get_or_init(|| (Instant::now(), monotonic_now()));
+    let at = match until.checked_duration_since(instant) {
+        Some(after) => monotonic.checked_add(after)?,
+        None => monotonic.saturating_sub(instant.duration_since(until))
+    };
+    Some(libc::timespec {
+        tv_sec: libc::time_t::try_from(at.as_secs()).ok()?,
+        tv_nsec: at.subsec_nanos() as _
+    })
+}
+
+/// Sleeps while `futex` holds `expected`, at most until `until`. Returns early, spuriously or
+/// because the value already differs, without saying which.
+///
+/// `FUTEX_WAIT_BITSET` takes an absolute `CLOCK_MONOTONIC` deadline, so however often the wait
+/// is resumed the deadline stays where it was set, and stepping the wall clock doesn't move it.
+/// Nor does it need the time `now` to measure a timeout from.
+pub(super) fn wait(futex: &AtomicU32, expected: u32, until: Option<Instant>, _now: Instant) {
+    // A deadline too far out for `timespec` is as good as none
+    let until = until.and_then(to_timespec);
+    let until = until.as_ref().map_or(ptr::null(), |until| until as *const libc::timespec);
+    // SAFETY: `futex` is a valid, aligned 32-bit word and `until` is null or points to a
+    // `timespec` that outlives the call. Every outcome sends the caller back to check `state`.
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            futex.as_ptr(),
+            libc::FUTEX_WAIT_BITSET | libc::FUTEX_PRIVATE_FLAG,
+            expected,
+            until,
+            ptr::null::<u32>(),
+            libc::FUTEX_BITSET_MATCH_ANY
+        )
+    };
+}
+
+pub(super) fn wake_one(futex: &AtomicU32) {
+    // SAFETY: `futex` is a valid, aligned 32-bit word
+    unsafe { libc::syscall(libc::SYS_futex, futex.as_ptr(), libc::FUTEX_WAKE | libc::FUTEX_PRIVATE_FLAG, 1) };
+}
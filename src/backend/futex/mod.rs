@@ -0,0 +1,56 @@
+//! Blocks with the platform's futex-like syscall, waiting on the state word itself
+//!
+//! The parked thread sleeps while `state` holds `PARKED`, and `unpark` wakes it after writing
+//! `NOTIFIED`, so an unpark that lands between the last check and the wait makes the wait return
+//! at once.
+
+use std::time::Instant;
+
+use crate::atomic::AtomicU32;
+use crate::clock::Clock;
+use crate::parker::{Wakeup, PARKED};
+use crate::watchdog::StallClock;
+
+#[cfg(parking_futex = "custom")]
+pub(crate) mod custom;
+#[cfg(parking_futex = "fuchsia")]
+mod fuchsia;
+#[cfg(parking_futex = "hermit")]
+mod hermit;
+#[cfg(parking_futex = "linux")]
+mod linux;
+#[cfg(parking_futex = "openbsd")]
+mod openbsd;
+#[cfg(parking_futex = "redox")]
+mod redox;
+
+#[cfg(parking_futex = "custom")]
+use custom as sys;
+#[cfg(parking_futex = "fuchsia")]
+use fuchsia as sys;
+#[cfg(parking_futex = "hermit")]
+use hermit as sys;
+#[cfg(parking_futex = "linux")]
+use linux as sys;
+#[cfg(parking_futex = "openbsd")]
+use openbsd as sys;
+#[cfg(parking_futex = "redox")]
+use redox as sys;
+
+/// The futex is the state word, so there is nothing else to keep
+pub(crate) struct Waiter;
+
+impl Waiter {
+
+    pub(crate) fn new() -> Waiter {
+        Waiter
+    }
+
+    pub(crate) fn park(&self, state: &AtomicU32, deadline: Option<Instant>, clock: Clock, stall: &mut Option<StallClock<'_>>) -> Wakeup {
+        super::sleep::park_with(state, deadline, clock, stall, |until, now| sys::wait(state, PARKED, until, now))
+    }
+
+    pub(crate) fn unpark(&self, state: &AtomicU32) {
+        sys::wake_one(state);
+    }
+}
@@ -0,0 +1,40 @@
+use std::convert::TryFrom;
+use std::ptr;
+use std::time::Instant;
+
+use crate::atomic::AtomicU32;
+
+/// Sleeps while `futex` holds `expected`, at most until `until`, which is `now` plus the
+/// timeout. Returns early, spuriously or because the value already differs, without saying
+/// which.
+///
+/// OpenBSD's `futex(2)` takes a relative timeout, measured on the monotonic clock, so it is
+/// recomputed from `until` on every call.
+pub(super) fn wait(futex: &AtomicU32, expected: u32, until: Option<Instant>, now: Instant) {
+    // A timeout too long for `timespec` is as good as none
+    let timeout = until.map(|until| until.saturating_duration_since(now)).and_then(|timeout| {
+        Some(libc::timespec {
+            tv_sec: libc::time_t::try_from(timeout.as_secs()).ok()?,
+            tv_nsec: timeout.subsec_nanos() as _
+        })
+    });
+    let timeout = timeout.as_ref().map_or(ptr::null(), |timeout| timeout as *const libc::timespec);
+    // SAFETY: `futex` is a valid, aligned 32-bit word and `timeout` is null or points to a
+    // `timespec` that outlives the call. Every outcome sends the caller back to check `state`.
+    unsafe {
+        libc::futex(
+            futex.as_ptr(),
+            libc::FUTEX_WAIT | libc::FUTEX_PRIVATE_FLAG,
+            expected as i32,
+            timeout,
+            ptr::null_mut()
+        )
+    };
+}
+
+pub(super) fn wake_one(futex: &AtomicU32) {
+    // SAFETY: `futex` is a valid, aligned 32-bit word
+    unsafe {
+        libc::futex(futex.as_ptr(), libc::FUTEX_WAKE | libc::FUTEX_PRIVATE_FLAG, 1, ptr::null(), ptr::null_mut())
+    };
+}
@@ -0,0 +1,28 @@
+use std::time::Instant;
+
+use syscall::data::TimeSpec;
+use syscall::flag::{FUTEX_WAIT, FUTEX_WAKE};
+
+use crate::atomic::AtomicU32;
+
+/// Sleeps while `futex` holds `expected`, at most until `until`, which is `now` plus the
+/// timeout. Returns early, spuriously or because the value already differs, without saying
+/// which.
+///
+/// Redox's `FUTEX_WAIT` takes a relative timeout, measured on the monotonic clock, so it is
+/// recomputed from `until` on every call.
+pub(super) fn wait(futex: &AtomicU32, expected: u32, until: Option<Instant>, now: Instant) {
+    let timeout = until.map(|until| until.saturating_duration_since(now)).map(|timeout| TimeSpec {
+        tv_sec: timeout.as_secs().min(i64::MAX as u64) as i64,
+        tv_nsec: timeout.subsec_nanos() as i32
+    });
+    let timeout = timeout.as_ref().map_or(0, |timeout| timeout as *const TimeSpec as usize);
+    // SAFETY: `futex` is a valid, aligned 32-bit word and `timeout` is zero or the address of a
+    // `TimeSpec` that outlives the call. Every outcome sends the caller back to check `state`.
+    let _ = unsafe { syscall::futex(futex.as_ptr().cast(), FUTEX_WAIT, expected as i32, timeout, std::ptr::null_mut()) };
+}
+
+pub(super) fn wake_one(futex: &AtomicU32) {
+    // SAFETY: `futex` is a valid, aligned 32-bit word
+    let _ = unsafe { syscall::futex(futex.as_ptr().cast(), FUTEX_WAKE, 1, 0, std::ptr::null_mut()) };
+}
@@ -0,0 +1,56 @@
+use std::convert::TryFrom;
+use std::ptr;
+use std::sync::atomic::AtomicI32;
+use std::sync::atomic::Ordering::SeqCst;
+use std::time::Instant;
+
+use crate::atomic::AtomicU32;
+use crate::clock::Clock;
+use crate::parker::Wakeup;
+use crate::watchdog::StallClock;
+
+/// Blocks the LWP with NetBSD's `_lwp_park` and wakes it with `_lwp_unpark`
+///
+/// An `_lwp_unpark` that arrives before the target parks makes its next `_lwp_park` return at
+/// once, so an unpark racing with the wait is never lost.
+pub(crate) struct Waiter {
+    /// The LWP currently in `park`, published before `state` becomes `PARKED`
+    lwp: AtomicI32
+}
+
+impl Waiter {
+
+    pub(crate) fn new() -> Waiter {
+        Waiter {
+            lwp: AtomicI32::new(0)
+        }
+    }
+
+    pub(crate) fn park(&self, state: &AtomicU32, deadline: Option<Instant>, clock: Clock, stall: &mut Option<StallClock<'_>>) -> Wakeup {
+        // A `Parker` is `Send`, so the LWP may differ from the last park
+        // SAFETY: `_lwp_self` has no preconditions
+        self.lwp.store(unsafe { libc::_lwp_self() }, SeqCst);
+
+        super::sleep::park_with(state, deadline, clock, stall, |until, now| {
+            // A timeout too long for `timespec` is as good as none
+            let mut timeout = until.map(|until| until.saturating_duration_since(now)).and_then(|timeout| {
+                Some(libc::timespec {
+                    tv_sec: libc::time_t::try_from(timeout.as_secs()).ok()?,
+                    tv_nsec: timeout.subsec_nanos() as _
+                })
+            });
+            // The monotonic clock is only consulted for a timeout, which the kernel may write
+            // back to, hence the mutable pointer
+            let timeout = timeout.as_mut().map_or(ptr::null_mut(), |timeout| timeout as *mut libc::timespec);
+            // SAFETY: `timeout` is null or points to a `timespec` that outlives the call. Every
+            // outcome sends `park_with` back to check `state`.
+            unsafe { libc::_lwp_park(libc::CLOCK_MONOTONIC, 0, timeout, 0, ptr::null(), ptr::null_mut()) };
+        })
+    }
+
+    pub(crate) fn unpark(&self, _state: &AtomicU32) {
+        // `park` publishes the LWP before `state` becomes `PARKED`, so it is always set here
+        // SAFETY: `_lwp_unpark` only looks the LWP up, failing if it has exited
+        unsafe { libc::_lwp_unpark(self.lwp.load(SeqCst), ptr::null()) };
+    }
+}
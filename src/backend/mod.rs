@@ -0,0 +1,63 @@
+//! Ways of blocking the parked thread
+//!
+//! `Inner` owns the `EMPTY`/`PARKED`/`NOTIFIED` state word and handles the paths that never
+//! block. Once it has to sleep it hands the state to the `Waiter` of the selected backend:
+//!
+//! * `condvar` (default): a `Mutex` + `Condvar` pair
+//! * `futex` (default on Linux, Android, Fuchsia, Hermit, OpenBSD and Redox, or with the
+//!   `custom-backend` feature): a futex-like wait, `FUTEX_WAIT_BITSET` on Linux and Android,
+//!   `zx_futex_wait` on Fuchsia, `sys_futex_wait` on Hermit, `futex(2)` on OpenBSD, the kernel's
+//!   `FUTEX_WAIT` on Redox, or the application's own `Backend`
+//! * `lwp` (default on NetBSD): `_lwp_park` and `_lwp_unpark`
+//! * `thread` (`thread-backend` feature): `std::thread::park_timeout` and `Thread::unpark`
+//! * `freertos` (`freertos-backend` feature, ESP-IDF only): FreeRTOS direct-to-task notifications
+//! * `single` (always on wasm without the `atomics` target feature): no blocking at all, as
+//!   there is no other thread to unpark
+//!
+//! `build.rs` picks one and sets `parking_backend` accordingly, plus `parking_futex` for the
+//! flavor of `futex`. `custom-backend` takes precedence over everything, then single-threaded
+//! targets, then the other backend features, then the target's default.
+//!
+//! Timed waits never depend on the wall clock: deadlines are `Instant`s, and the blocking
+//! primitives measure their timeouts against a monotonic clock, directly in the case of
+//! `futex`, and through std, which sets up its condition variables that way, otherwise.
+//!
+//! Every backend provides the same interface:
+//!
+//! * `Waiter::new()`
+//! * `Waiter::park(&self, state, deadline, clock, &mut stall) -> Wakeup` moves `state` from
+//!   `EMPTY` to `PARKED`, blocks and returns `state` to `EMPTY`. `clock` is the parker's
+//!   `Clock`: relative timeouts are computed from `clock.now()`, and whether `deadline` has
+//!   passed is decided with `clock.reached`, which confirms a coarse reading with a precise one
+//! * `Waiter::unpark(&self, state)` wakes the parked thread after `state` was swapped from
+//!   `PARKED` to `NOTIFIED`
+
+#[cfg(parking_backend = "condvar")]
+mod condvar;
+#[cfg(parking_backend = "freertos")]
+mod freertos;
+#[cfg(parking_backend = "futex")]
+mod futex;
+#[cfg(parking_backend = "lwp")]
+mod lwp;
+#[cfg(parking_backend = "single")]
+mod single;
+#[cfg(not(parking_backend = "condvar"))]
+mod sleep;
+#[cfg(parking_backend = "thread")]
+mod thread;
+
+#[cfg(parking_backend = "condvar")]
+pub(crate) use condvar::Waiter;
+#[cfg(parking_backend = "freertos")]
+pub(crate) use freertos::Waiter;
+#[cfg(parking_backend = "futex")]
+pub(crate) use futex::Waiter;
+#[cfg(parking_backend = "lwp")]
+pub(crate) use lwp::Waiter;
+#[cfg(parking_futex = "custom")]
+pub use futex::custom::{set_backend, Backend};
+#[cfg(parking_backend = "single")]
+pub(crate) use single::Waiter;
+#[cfg(parking_backend = "thread")]
+pub(crate) use thread::Waiter;
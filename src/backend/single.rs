@@ -0,0 +1,43 @@
+use std::thread;
+use std::time::Instant;
+
+use crate::atomic::AtomicU32;
+use crate::clock::Clock;
+use crate::parker::Wakeup;
+use crate::watchdog::StallClock;
+
+/// Blocks on targets with a single thread, where nothing but the parked thread itself could
+/// ever unpark
+///
+/// A timed park sleeps out its timeout. An untimed park can never return, so it panics, unless
+/// the `single-threaded-spin` feature asks it to busy-yield instead, for hosts that can still
+/// deliver an unpark, e.g. from a stall watchdog callback.
+pub(crate) struct Waiter;
+
+impl Waiter {
+
+    pub(crate) fn new() -> Waiter {
+        Waiter
+    }
+
+    pub(crate) fn park(&self, state: &AtomicU32, deadline: Option<Instant>, clock: Clock, stall: &mut Option<StallClock<'_>>) -> Wakeup {
+        if deadline.is_none() && !cfg!(feature = "single-threaded-spin") {
+            panic!(
+                "`park` without a pending notification would block forever on a single-threaded \
+                target, enable the `single-threaded-spin` feature to busy-yield instead"
+            );
+        }
+
+        super::sleep::park_with(state, deadline, clock, stall, |until, now| match until {
+            // Nothing can interrupt a sleep here, so only sleep in the timed case
+            Some(until) if !cfg!(feature = "single-threaded-spin") => {
+                thread::sleep(until.saturating_duration_since(now))
+            }
+            _ => thread::yield_now()
+        })
+    }
+
+    pub(crate) fn unpark(&self, _state: &AtomicU32) {
+        // The only thread is the one unparking
+    }
+}
@@ -0,0 +1,67 @@
+use std::sync::atomic::Ordering::SeqCst;
+use std::time::Instant;
+
+use crate::atomic::AtomicU32;
+use crate::clock::Clock;
+use crate::parker::{Wakeup, EMPTY, NOTIFIED, PARKED};
+use crate::watchdog::StallClock;
+
+/// Parks for backends whose primitive is "sleep until woken or until a point in time", where
+/// wakeups may be spurious or left over from an earlier park
+///
+/// `sleep(None, now)` sleeps until woken, `sleep(Some(until), now)` at most until `until`, where
+/// `now` is the time on `clock` to measure a relative timeout from. The backend must have
+/// published whatever `unpark` needs to find the thread before calling this.
+pub(crate) fn park_with<F>(
+    state: &AtomicU32,
+    deadline: Option<Instant>,
+    clock: Clock,
+    stall: &mut Option<StallClock<'_>>,
+    mut sleep: F
+) -> Wakeup
+    where F: FnMut(Option<Instant>, Instant)
+{
+    match state.compare_exchange(EMPTY, PARKED, SeqCst, SeqCst) {
+        Ok(_) => {},
+        // Consume this notification to avoid spurious wakeups in the next park
+        Err(NOTIFIED) => {
+            let old = state.swap(EMPTY, SeqCst);
+            assert_eq!(old, NOTIFIED, "park state changed unexpectedly");
+            return Wakeup::notified(0);
+        }
+        Err(n) => panic!("inconsistent park_timeout state: {}", n)
+    }
+
+    let mut spurious = 0;
+    let mut now = clock.now();
+    loop {
+        // The primitive may return spuriously, including for wakeups left by an unpark that
+        // raced with an earlier park, so `state` is the only source of truth
+        let until = match (deadline, stall.as_ref()) {
+            (Some(deadline), Some(stall)) => Some(deadline.min(stall.next_report())),
+            (Some(deadline), None) => Some(deadline),
+            (None, Some(stall)) => Some(stall.next_report()),
+            (None, None) => None
+        };
+        sleep(until, now);
+
+        if state.compare_exchange(NOTIFIED, EMPTY, SeqCst, SeqCst).is_ok() {
+            return Wakeup::notified(spurious);
+        }
+
+        now = clock.now();
+        if let Some(deadline) = deadline {
+            if clock.reached(&mut now, deadline) {
+                return match state.swap(EMPTY, SeqCst) {
+                    NOTIFIED => Wakeup::notified(spurious),
+                    PARKED => Wakeup::timed_out(spurious),
+                    n => panic!("inconsistent park_timeout state: {}", n)
+                };
+            }
+        }
+        match stall.as_mut() {
+            Some(stall) if now >= stall.next_report() => stall.report(),
+            _ => spurious += 1
+        }
+    }
+}
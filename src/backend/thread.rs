@@ -0,0 +1,46 @@
+use std::sync::Mutex;
+use std::thread::{self, Thread};
+use std::time::Instant;
+
+use crate::atomic::AtomicU32;
+use crate::clock::Clock;
+use crate::parker::Wakeup;
+use crate::watchdog::StallClock;
+
+/// Blocks in `std::thread::park`, leaving the heavy lifting to std's own parker
+pub(crate) struct Waiter {
+    /// The thread currently in `park`, published before `state` becomes `PARKED`
+    thread: Mutex<Option<Thread>>
+}
+
+impl Waiter {
+
+    pub(crate) fn new() -> Waiter {
+        Waiter {
+            thread: Mutex::new(None)
+        }
+    }
+
+    pub(crate) fn park(&self, state: &AtomicU32, deadline: Option<Instant>, clock: Clock, stall: &mut Option<StallClock<'_>>) -> Wakeup {
+        // A `Parker` is `Send`, so the thread may differ from the last park
+        {
+            let mut thread = self.thread.lock().unwrap();
+            let current = thread::current();
+            if thread.as_ref().map(Thread::id) != Some(current.id()) {
+                *thread = Some(current);
+            }
+        }
+
+        super::sleep::park_with(state, deadline, clock, stall, |until, now| match until {
+            None => thread::park(),
+            Some(until) => thread::park_timeout(until.saturating_duration_since(now))
+        })
+    }
+
+    pub(crate) fn unpark(&self, _state: &AtomicU32) {
+        // `park` publishes the thread before `state` becomes `PARKED`, so it is always set here
+        if let Some(thread) = self.thread.lock().unwrap().as_ref() {
+            thread.unpark();
+        }
+    }
+}
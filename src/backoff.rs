@@ -0,0 +1,80 @@
+use std::hint;
+use std::thread;
+use std::time::Duration;
+
+use crate::Parker;
+
+/// Steps that spin, each spinning twice as long as the one before
+const SPIN_LIMIT: u32 = 6;
+/// Steps up to this one yield the thread instead
+const YIELD_LIMIT: u32 = 10;
+/// Timeout of the first step that parks, doubling with every step after until `PARK_MAX`
+const PARK_BASE: Duration = Duration::from_micros(10);
+const PARK_MAX: Duration = Duration::from_millis(10);
+/// Doublings of `PARK_BASE` it takes to pass `PARK_MAX`
+const PARK_DOUBLINGS: u32 = 10;
+
+/// Waits a little longer on every retry of a contended operation: spinning, then yielding, then
+/// parking with growing timeouts
+///
+/// Call `snooze` after every failed attempt and `reset` after a successful one. Retrying as
+/// soon as `snooze` returns keeps working without any unpark, but unparking the parker passed to
+/// `snooze` when the awaited state changes ends the wait early once the backoff has reached
+/// parking.
+#[derive(Debug, Clone, Default)]
+pub struct Backoff {
+    step: u32
+}
+
+impl Backoff {
+
+    pub fn new() -> Backoff {
+        Backoff::default()
+    }
+
+    /// Starts over from the shortest wait
+    pub fn reset(&mut self) {
+        self.step = 0;
+    }
+
+    /// Spins for a moment, for retries that only ever need a short wait
+    ///
+    /// Never yields or parks. Spins longer each time until the spinning limit is reached.
+    pub fn spin(&mut self) {
+        for _ in 0..1 << self.step.min(SPIN_LIMIT) {
+            hint::spin_loop();
+        }
+        if self.step <= SPIN_LIMIT {
+            self.step += 1;
+        }
+    }
+
+    /// Waits before the next retry, longer each time: spinning at first, then yielding the
+    /// thread, and then parking on `parker` with a timeout that doubles up to 10ms
+    ///
+    /// return `true` if a park ended because `parker` was unparked
+    pub fn snooze(&mut self, parker: &Parker) -> bool {
+        let step = self.step;
+        if step <= YIELD_LIMIT + PARK_DOUBLINGS {
+            self.step += 1;
+        }
+        if step <= SPIN_LIMIT {
+            for _ in 0..1 << step {
+                hint::spin_loop();
+            }
+            false
+        } else if step <= YIELD_LIMIT {
+            thread::yield_now();
+            false
+        } else {
+            let timeout = PARK_BASE * (1 << (step - YIELD_LIMIT - 1));
+            parker.park_timeout(timeout.min(PARK_MAX))
+        }
+    }
+
+    /// Return `true` once `snooze` parks, a sign the wait is long enough that blocking on
+    /// something better suited, such as a lock or channel, may be worth it
+    pub fn is_parking(&self) -> bool {
+        self.step > YIELD_LIMIT
+    }
+}
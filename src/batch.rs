@@ -0,0 +1,153 @@
+use std::fmt::Formatter;
+
+use crate::Unparker;
+
+/// Collects unparkers to notify later, all at once
+///
+/// Push unparkers while holding a lock of your own, then `flush` once it's released, so woken
+/// threads don't immediately block on that lock. A batch dropped without `flush` flushes itself,
+/// so no wakeup is lost.
+#[derive(Default)]
+pub struct UnparkBatch {
+    unparkers: Vec<Unparker>
+}
+
+impl UnparkBatch {
+
+    pub fn new() -> UnparkBatch {
+        UnparkBatch::default()
+    }
+
+    /// Creates a batch with room for `capacity` unparkers before it reallocates
+    pub fn with_capacity(capacity: usize) -> UnparkBatch {
+        UnparkBatch {
+            unparkers: Vec::with_capacity(capacity)
+        }
+    }
+
+    /// Adds `unparker` to be notified on the next `flush`
+    pub fn push(&mut self, unparker: Unparker) {
+        self.unparkers.push(unparker);
+    }
+
+    /// Notifies every pushed parker and empties the batch, keeping its allocation
+    ///
+    /// return the number of parkers this call was the first to notify
+    pub fn flush(&mut self) -> usize {
+        self.unparkers.drain(..).filter(|u| u.unpark()).count()
+    }
+
+    /// Return the number of unparkers waiting for `flush`
+    pub fn len(&self) -> usize {
+        self.unparkers.len()
+    }
+
+    /// Return `true` if no unparkers are waiting for `flush`
+    pub fn is_empty(&self) -> bool {
+        self.unparkers.is_empty()
+    }
+}
+
+impl Extend<Unparker> for UnparkBatch {
+    fn extend<I: IntoIterator<Item = Unparker>>(&mut self, iter: I) {
+        self.unparkers.extend(iter);
+    }
+}
+
+impl Drop for UnparkBatch {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl std::fmt::Debug for UnparkBatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("UnparkBatch { .. }")
+    }
+}
+
+/// A notification owed to a parker, delivered by `commit` or on drop
+///
+/// Created by `Unparker::unpark_deferred`, the single-unparker counterpart of `UnparkBatch`.
+#[must_use = "dropping the guard unparks right away"]
+pub struct DeferredUnpark {
+    unparker: Option<Unparker>
+}
+
+impl Unparker {
+
+    /// Return a guard that notifies the parker when committed or dropped, for marking a wakeup
+    /// as owed inside a critical section and delivering it after
+    pub fn unpark_deferred(&self) -> DeferredUnpark {
+        DeferredUnpark {
+            unparker: Some(self.clone())
+        }
+    }
+}
+
+impl DeferredUnpark {
+
+    /// Notifies the parker now
+    ///
+    /// return `true` if this call is the first to notify the parker, or `false`
+    /// if the parker was already notified
+    pub fn commit(mut self) -> bool {
+        self.unparker.take().is_some_and(|u| u.unpark())
+    }
+}
+
+impl Drop for DeferredUnpark {
+    fn drop(&mut self) {
+        if let Some(u) = self.unparker.take() {
+            u.unpark();
+        }
+    }
+}
+
+impl std::fmt::Debug for DeferredUnpark {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("DeferredUnpark { .. }")
+    }
+}
+
+/// Notifies a parker when dropped, including while unwinding
+///
+/// Created by `Unparker::unpark_on_drop`. Held by a worker for the length of a task, it makes
+/// sure a coordinator parked on the worker's progress wakes up to see the task ended, even if
+/// the task panicked. Unlike `DeferredUnpark`, it can't deliver the notification early.
+#[must_use = "dropping the guard unparks right away"]
+pub struct UnparkOnDrop {
+    unparker: Option<Unparker>
+}
+
+impl Unparker {
+
+    /// Return a guard that notifies the parker when dropped
+    pub fn unpark_on_drop(&self) -> UnparkOnDrop {
+        UnparkOnDrop {
+            unparker: Some(self.clone())
+        }
+    }
+}
+
+impl UnparkOnDrop {
+
+    /// Gives the unparker back without notifying the parker
+    pub fn disarm(mut self) -> Unparker {
+        self.unparker.take().expect("guard already disarmed")
+    }
+}
+
+impl Drop for UnparkOnDrop {
+    fn drop(&mut self) {
+        if let Some(u) = self.unparker.take() {
+            u.unpark();
+        }
+    }
+}
+
+impl std::fmt::Debug for UnparkOnDrop {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("UnparkOnDrop { .. }")
+    }
+}
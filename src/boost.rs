@@ -0,0 +1,114 @@
+//! Priority boosts for parked threads on Windows, see `ParkerBuilder::priority_boost`
+
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::{Mutex, PoisonError};
+
+type Handle = *mut c_void;
+
+const THREAD_SET_INFORMATION: u32 = 0x0020;
+const THREAD_QUERY_INFORMATION: u32 = 0x0040;
+const THREAD_PRIORITY_HIGHEST: i32 = 2;
+const THREAD_PRIORITY_ERROR_RETURN: i32 = 0x7fff_ffff;
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn GetCurrentThread() -> Handle;
+    fn GetCurrentThreadId() -> u32;
+    fn OpenThread(access: u32, inherit: i32, thread_id: u32) -> Handle;
+    fn CloseHandle(handle: Handle) -> i32;
+    fn GetThreadPriority(thread: Handle) -> i32;
+    fn SetThreadPriority(thread: Handle, priority: i32) -> i32;
+}
+
+/// Raises the parked thread's priority when it is unparked, until it returns from the park
+pub(crate) struct PriorityBoost {
+    state: Mutex<State>
+}
+
+struct State {
+    /// Identifier of the thread that last parked, and a handle to it that other threads can use
+    thread_id: u32,
+    thread: Handle,
+    /// Whether that thread is blocked
+    parked: bool,
+    /// Priority to restore once it returns, if it was boosted
+    restore: Option<i32>
+}
+
+// SAFETY: `thread` is a real handle, usable from any thread, and only closed on drop
+unsafe impl Send for State {}
+
+impl PriorityBoost {
+
+    pub(crate) fn new() -> PriorityBoost {
+        PriorityBoost {
+            state: Mutex::new(State {
+                thread_id: 0,
+                thread: ptr::null_mut(),
+                parked: false,
+                restore: None
+            })
+        }
+    }
+
+    /// Called by the parking thread before it can be seen as `PARKED`
+    pub(crate) fn begin_park(&self) {
+        let mut state = self.state.lock().unwrap();
+        // SAFETY: always safe to call
+        let thread_id = unsafe { GetCurrentThreadId() };
+        // The parker may move between threads, so the handle is kept for as long as it doesn't
+        if state.thread.is_null() || state.thread_id != thread_id {
+            if !state.thread.is_null() {
+                // SAFETY: `thread` is a handle from `OpenThread`, closed once
+                unsafe { CloseHandle(state.thread) };
+            }
+            state.thread_id = thread_id;
+            // SAFETY: opens the current thread, failing with a null handle, which disables boosts
+            state.thread = unsafe { OpenThread(THREAD_SET_INFORMATION | THREAD_QUERY_INFORMATION, 0, thread_id) };
+        }
+        state.parked = true;
+    }
+
+    /// Called by the parking thread once it has stopped blocking, dropping any boost
+    pub(crate) fn end_park(&self) {
+        let restore = {
+            let mut state = self.state.lock().unwrap();
+            state.parked = false;
+            state.restore.take()
+        };
+        if let Some(priority) = restore {
+            // SAFETY: the pseudo handle refers to the current thread
+            unsafe { SetThreadPriority(GetCurrentThread(), priority) };
+        }
+    }
+
+    /// Called by an unparking thread that found the parker `PARKED`, before waking it
+    pub(crate) fn boost(&self) {
+        let mut state = self.state.lock().unwrap();
+        // Once `end_park` takes the lock no boost starts, so none outlives the park
+        if !state.parked || state.restore.is_some() || state.thread.is_null() {
+            return;
+        }
+        // SAFETY: `thread` is an open handle with the access rights these calls need
+        unsafe {
+            let priority = GetThreadPriority(state.thread);
+            if priority != THREAD_PRIORITY_ERROR_RETURN
+                && priority < THREAD_PRIORITY_HIGHEST
+                && SetThreadPriority(state.thread, THREAD_PRIORITY_HIGHEST) != 0
+            {
+                state.restore = Some(priority);
+            }
+        }
+    }
+}
+
+impl Drop for PriorityBoost {
+    fn drop(&mut self) {
+        let state = self.state.get_mut().unwrap_or_else(PoisonError::into_inner);
+        if !state.thread.is_null() {
+            // SAFETY: `thread` is a handle from `OpenThread`, closed once
+            unsafe { CloseHandle(state.thread) };
+        }
+    }
+}
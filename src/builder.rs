@@ -0,0 +1,166 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(windows)]
+use crate::boost::PriorityBoost;
+#[cfg(windows)]
+use crate::hires::HighResTimer;
+use crate::clock::Clock;
+use crate::hook::ParkHook;
+#[cfg(feature = "mock-clock")]
+use crate::mock::MockClock;
+use crate::parker::Inner;
+#[cfg(target_vendor = "apple")]
+use crate::qos::QosOverride;
+use crate::watchdog::{Stall, Watchdog};
+use crate::{Parker, ParkerPool};
+
+/// Configures a `Parker` before creating it
+///
+/// `Parker::new()` is the same as `ParkerBuilder::new().build()`.
+#[derive(Debug, Clone, Default)]
+pub struct ParkerBuilder {
+    clock: Clock,
+    spins: u32,
+    #[cfg(target_vendor = "apple")]
+    qos_override: bool,
+    #[cfg(windows)]
+    priority_boost: bool,
+    #[cfg(windows)]
+    high_resolution: bool,
+    watchdog: Option<Watchdog>,
+    hook: Option<ParkHook>,
+    #[cfg(feature = "mock-clock")]
+    mock_clock: Option<MockClock>
+}
+
+impl ParkerBuilder {
+
+    pub fn new() -> ParkerBuilder {
+        ParkerBuilder::default()
+    }
+
+    /// Selects the clock timed parks measure their timeouts against, `Clock::Monotonic` by default
+    pub fn clock(mut self, clock: Clock) -> ParkerBuilder {
+        self.clock = clock;
+        self
+    }
+
+    /// Measures timeouts on `clock` instead, which only moves when advanced, see `MockClock`
+    ///
+    /// Takes precedence over `clock`.
+    #[cfg(feature = "mock-clock")]
+    pub fn mock_clock(mut self, clock: &MockClock) -> ParkerBuilder {
+        self.mock_clock = Some(clock.clone());
+        self
+    }
+
+    /// Polls for a notification up to `spins` times before blocking, none by default
+    ///
+    /// A notification that arrives soon after parking is then picked up without the cost of
+    /// sleeping and being woken. How long each poll waits depends on the CPU: about a
+    /// microsecond in a low-power state on x86-64 CPUs with WAITPKG, up to about 100
+    /// microseconds in one on aarch64 Linux, where only the kernel's timer event stream bounds
+    /// it, and a single spin-loop hint elsewhere. A notification ends the first two early.
+    /// Spinning doesn't count towards timeouts.
+    pub fn spin(mut self, spins: u32) -> ParkerBuilder {
+        self.spins = spins;
+        self
+    }
+
+    /// Lets an unpark from a thread of higher QoS class raise the parked thread to that class
+    /// until it returns from the park, off by default
+    ///
+    /// This keeps a high-QoS thread from waiting on a low-QoS thread it woke which the scheduler
+    /// is slow to run, the priority inversion Apple's own locks avoid the same way. The override
+    /// costs a lock and two QoS lookups on every unpark that wakes a thread.
+    #[cfg(target_vendor = "apple")]
+    pub fn qos_override(mut self, enabled: bool) -> ParkerBuilder {
+        self.qos_override = enabled;
+        self
+    }
+
+    /// Lets an unpark raise the parked thread to `THREAD_PRIORITY_HIGHEST` until it returns from
+    /// the park, off by default
+    ///
+    /// The boosted thread preempts most others as soon as it's woken, which shortens the time
+    /// from unpark to running under load. Threads already at that priority or above are left
+    /// alone. The boost costs a lock and two priority changes on every unpark that wakes a thread.
+    #[cfg(windows)]
+    pub fn priority_boost(mut self, enabled: bool) -> ParkerBuilder {
+        self.priority_boost = enabled;
+        self
+    }
+
+    /// Times timed parks with a high-resolution waitable timer, off by default
+    ///
+    /// Timeouts then end within a fraction of a millisecond of their deadline, rather than
+    /// being rounded up to the scheduler tick of about 15.6 ms. Windows before 10 1803 has no
+    /// high-resolution timers and gets 1 ms accuracy instead, by raising the timer resolution
+    /// while a timed park waits. Each parker holds an event and a timer handle for this, and
+    /// every unpark that wakes a thread also sets the event.
+    #[cfg(windows)]
+    pub fn high_resolution(mut self, enabled: bool) -> ParkerBuilder {
+        self.high_resolution = enabled;
+        self
+    }
+
+    /// Invokes `callback` whenever a park has been blocked for another `threshold`, while
+    /// continuing to wait
+    ///
+    /// The callback runs on the parked thread, without any of the parker's locks held, so it may
+    /// unpark the parker itself. A zero `threshold` disables the watchdog. If the callback panics,
+    /// the park unwinds and leaves the parker unparked, keeping any notification for the next park.
+    pub fn watchdog<F>(mut self, threshold: Duration, callback: F) -> ParkerBuilder
+        where F: Fn(&Stall<'_>) + Send + Sync + 'static
+    {
+        self.watchdog = if threshold == Duration::from_millis(0) {
+            None
+        } else {
+            Some(Watchdog { threshold, callback: Arc::new(callback) })
+        };
+        self
+    }
+
+    /// Runs `hook` on the parking thread right before each park would block, passing the time
+    /// left until the deadline, or `None` for an untimed park
+    ///
+    /// This lets the thread poll an I/O reactor with the rest of its park budget instead of
+    /// dedicating another thread to it. The park checks for a notification and for its deadline
+    /// again once the hook returns, and only blocks if there is neither, so a hook that waits
+    /// out the timeout itself ends the park. A hook that found events for the parking thread
+    /// should unpark the parker to end the park. Spin polls from `spin` come first.
+    pub fn park_hook<F>(mut self, hook: F) -> ParkerBuilder
+        where F: Fn(Option<Duration>) + Send + Sync + 'static
+    {
+        self.hook = Some(ParkHook(Arc::new(hook)));
+        self
+    }
+
+    /// Creates a pool of parkers configured like this, see `ParkerPool`
+    pub fn pool(self, capacity: usize) -> ParkerPool {
+        ParkerPool::from_builder(self, capacity)
+    }
+
+    /// Creates the parker
+    pub fn build(self) -> Parker {
+        Parker::from_inner(Arc::new(self.build_inner()))
+    }
+
+    pub(crate) fn build_inner(self) -> Inner {
+        Inner::new(
+            self.clock,
+            self.spins,
+            #[cfg(target_vendor = "apple")]
+            self.qos_override.then(QosOverride::new),
+            #[cfg(windows)]
+            self.priority_boost.then(PriorityBoost::new),
+            #[cfg(windows)]
+            self.high_resolution.then(HighResTimer::new).flatten(),
+            self.watchdog,
+            self.hook,
+            #[cfg(feature = "mock-clock")]
+            self.mock_clock
+        )
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt::Formatter;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::Arc;
+
+use crate::atomic::AtomicBool;
+use crate::{MultiUnparker, Parker};
+
+/// Tells parkers waiting in `Parker::park_cancellable` to give up
+///
+/// Clones share the same cancellation state. Once cancelled, a token stays cancelled.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    shared: Arc<Shared>
+}
+
+#[derive(Default)]
+struct Shared {
+    cancelled: AtomicBool,
+    /// Parkers currently in `park_cancellable` with this token
+    waiters: MultiUnparker
+}
+
+/// Returned by `Parker::park_cancellable` when the token was cancelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cancelled;
+
+/// Cancels its token when dropped, including while unwinding
+///
+/// Created by `CancellationToken::guard`.
+#[must_use = "dropping the guard cancels the token right away"]
+pub struct CancelGuard {
+    token: Option<CancellationToken>
+}
+
+impl CancellationToken {
+
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancels the token, waking every parker waiting on it
+    pub fn cancel(&self) {
+        self.shared.cancelled.store(true, SeqCst);
+        self.shared.waiters.unpark_all();
+    }
+
+    /// Return `true` if the token has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.shared.cancelled.load(SeqCst)
+    }
+
+    /// Return a guard that cancels the token when dropped
+    pub fn guard(&self) -> CancelGuard {
+        CancelGuard {
+            token: Some(self.clone())
+        }
+    }
+}
+
+impl CancelGuard {
+
+    /// Gives the token back without cancelling it
+    pub fn disarm(mut self) -> CancellationToken {
+        self.token.take().expect("guard already disarmed")
+    }
+}
+
+impl Drop for CancelGuard {
+    fn drop(&mut self) {
+        if let Some(token) = self.token.take() {
+            token.cancel();
+        }
+    }
+}
+
+impl Parker {
+
+    /// Blocks until notified or until `token` is cancelled, and then goes back into unnotified
+    /// state
+    ///
+    /// return `Err(Cancelled)` if `token` is cancelled, even if a notification arrived too. A
+    /// token cancelled before the call returns right away and leaves any pending notification for
+    /// the next park. A cancellation that races with the return may leave one behind as well.
+    pub fn park_cancellable(&self, token: &CancellationToken) -> Result<(), Cancelled> {
+        // Register before checking, so that a cancellation after the check wakes the park
+        let key = token.shared.waiters.add(self.unparker());
+        let result = if token.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            self.park();
+            if token.is_cancelled() { Err(Cancelled) } else { Ok(()) }
+        };
+        token.shared.waiters.remove(key);
+        result
+    }
+}
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("park cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+impl std::fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("CancellationToken { .. }")
+    }
+}
+
+impl std::fmt::Debug for CancelGuard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("CancelGuard { .. }")
+    }
+}
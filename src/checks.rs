@@ -0,0 +1,70 @@
+//! Panics in place of the silent hangs that misusing a parker otherwise ends in
+
+use std::sync::{Mutex, PoisonError};
+use std::thread::{self, Thread};
+
+/// The thread currently in a park of one parker
+pub(crate) struct Owner(Mutex<Option<Thread>>);
+
+/// Marks a park as in progress until dropped
+pub(crate) struct Parking<'a>(&'a Owner);
+
+impl Owner {
+
+    pub(crate) const fn new() -> Owner {
+        Owner(Mutex::new(None))
+    }
+
+    /// Claims the parker `id` for a park by the current thread
+    ///
+    /// Panics if a park of it is already in progress, which would otherwise have the two parks
+    /// race for one notification and the loser sleep through it, or, on the same thread, the
+    /// outer park block after the inner one consumed it.
+    pub(crate) fn enter(&self, id: usize) -> Parking<'_> {
+        let current = thread::current();
+        let mut owner = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(other) = owner.as_ref() {
+            let message = if other.id() == current.id() {
+                format!(
+                    "parker {} parked again on thread '{}' while already parking, likely from \
+                     its park hook",
+                    id, name(&current)
+                )
+            } else {
+                format!(
+                    "parker {} parked on thread '{}' while thread '{}' is parking it",
+                    id, name(&current), name(other)
+                )
+            };
+            drop(owner);
+            panic!("{}", message);
+        }
+        *owner = Some(current);
+        Parking(self)
+    }
+
+    /// Panics if the current thread is parking the parker `id`, where `unpark_now` always finds
+    /// it not yet blocked and drops the notification the thread then blocks waiting for
+    pub(crate) fn check_unpark_now(&self, id: usize) {
+        let current = thread::current();
+        let parking = self.0.lock().unwrap_or_else(PoisonError::into_inner)
+            .as_ref().is_some_and(|owner| owner.id() == current.id());
+        if parking {
+            panic!(
+                "parker {} unparked with `unpark_now` by thread '{}' while parking it, likely from \
+                 its park hook, which loses the notification; use `unpark`",
+                id, name(&current)
+            );
+        }
+    }
+}
+
+impl Drop for Parking<'_> {
+    fn drop(&mut self) {
+        *self.0.0.lock().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+}
+
+fn name(thread: &Thread) -> &str {
+    thread.name().unwrap_or("<unnamed>")
+}
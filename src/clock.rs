@@ -0,0 +1,119 @@
+#[cfg(any(target_os = "linux", target_os = "android", target_vendor = "apple"))]
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Clock that timed parks measure their timeouts against
+///
+/// Set with `ParkerBuilder::clock`. Whatever the clock, stepping the wall clock never affects
+/// timed parks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum Clock {
+    /// The clock behind `Instant`, which doesn't advance while the system is suspended, so
+    /// suspending through a timed park extends it by the time spent suspended
+    #[default]
+    Monotonic,
+    /// `CLOCK_BOOTTIME` on Linux and Android, which keeps counting while the system is
+    /// suspended, so a timed park ends as soon as possible after resuming past its deadline
+    ///
+    /// As the blocking primitives can't wait on this clock, parks wait in slices of at most
+    /// `BOOTTIME_SLICE` and check the clock in between. Same as `Monotonic` on other platforms.
+    Boottime,
+    /// A clock that is cheaper to read but only advances once per scheduler tick, for frequent
+    /// short timed parks where a few milliseconds late is fine
+    ///
+    /// `CLOCK_MONOTONIC_COARSE` on Linux and Android and `CLOCK_UPTIME_RAW_APPROX` on Apple
+    /// platforms, with ticks typically 1 to 10 ms apart. Timeouts start from a precise reading
+    /// and the waits check the coarse clock, which never runs ahead, so they never end early
+    /// but may run up to a tick long. A deadline the coarse clock hasn't reached when a wait
+    /// ends is checked with one precise reading before waiting again, so a clock that a
+    /// tickless kernel let fall further behind doesn't hold the park up either. Like
+    /// `Monotonic`, this doesn't count time spent suspended. Same as `Monotonic` on other
+    /// platforms.
+    Coarse
+}
+
+impl Clock {
+
+    /// Return the time on this clock, which for `Coarse` lags behind `Instant::now()`
+    pub(crate) fn now(self) -> Instant {
+        if self == Clock::Coarse {
+            if let Some(now) = coarse() {
+                return now;
+            }
+        }
+        Instant::now()
+    }
+
+    /// Return `true` if `deadline` has passed at `now`, a reading of this clock
+    ///
+    /// A coarse reading that hasn't reached `deadline` is replaced with a precise one, which
+    /// decides instead.
+    pub(crate) fn reached(self, now: &mut Instant, deadline: Instant) -> bool {
+        if *now >= deadline {
+            return true;
+        }
+        if self == Clock::Coarse && cfg!(any(target_os = "linux", target_os = "android", target_vendor = "apple")) {
+            *now = Instant::now();
+        }
+        *now >= deadline
+    }
+}
+
+/// Longest a park with `Clock::Boottime` blocks before checking the clock again, which bounds
+/// how late it notices a deadline that passed during suspend
+pub(crate) const BOOTTIME_SLICE: Duration = Duration::from_millis(100);
+
+/// Return the time since boot, including time spent suspended, or `None` where that clock isn't
+/// available
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn boottime() -> Option<Duration> {
+    read(libc::CLOCK_BOOTTIME)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub(crate) fn boottime() -> Option<Duration> {
+    None
+}
+
+/// The clock behind `Instant` and its coarse counterpart
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const COARSE: (libc::clockid_t, libc::clockid_t) = (libc::CLOCK_MONOTONIC, libc::CLOCK_MONOTONIC_COARSE);
+#[cfg(target_vendor = "apple")]
+const COARSE: (libc::clockid_t, libc::clockid_t) = (libc::CLOCK_UPTIME_RAW, libc::CLOCK_UPTIME_RAW_APPROX);
+
+/// An `Instant` and the reading of its clock taken alongside it, to translate coarse readings
+/// into `Instant`s
+///
+/// The `Instant` is taken first, so translated readings err on the early side and never run
+/// ahead of `Instant::now()`.
+#[cfg(any(target_os = "linux", target_os = "android", target_vendor = "apple"))]
+static COARSE_ANCHOR: OnceLock<Option<(Instant, Duration)>> = OnceLock::new();
+
+/// Return the time on the coarse clock, which lags behind `Instant::now()`, or `None` where
+/// there is no coarse clock
+#[cfg(any(target_os = "linux", target_os = "android", target_vendor = "apple"))]
+fn coarse() -> Option<Instant> {
+    let &(instant, precise) = COARSE_ANCHOR.get_or_init(|| Some((Instant::now(), read(COARSE.0)?))).as_ref()?;
+    let now = read(COARSE.1)?;
+    let now = match now.checked_sub(precise) {
+        Some(after) => instant.checked_add(after)?,
+        None => instant.checked_sub(precise - now)?
+    };
+    Some(now)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_vendor = "apple")))]
+fn coarse() -> Option<Instant> {
+    None
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_vendor = "apple"))]
+fn read(clock: libc::clockid_t) -> Option<Duration> {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: `ts` is a valid `timespec` to write to
+    if unsafe { libc::clock_gettime(clock, &mut ts) } != 0 {
+        return None;
+    }
+    Some(Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
+}
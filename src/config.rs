@@ -0,0 +1,15 @@
+//! Build-time configuration read from environment variables
+
+/// Parses a tick rate override such as `PARKING_FREERTOS_TICK_RATE_HZ`
+pub(crate) const fn parse_rate(value: &str) -> u64 {
+    let bytes = value.as_bytes();
+    let mut rate = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        assert!(bytes[i].is_ascii_digit(), "tick rate overrides must be decimal numbers");
+        rate = rate * 10 + (bytes[i] - b'0') as u64;
+        i += 1;
+    }
+    assert!(rate > 0, "tick rate overrides must not be zero");
+    rate
+}
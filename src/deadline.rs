@@ -0,0 +1,125 @@
+use std::time::{Duration, Instant};
+
+/// When a park gives up waiting: at an `Instant`, or never
+///
+/// Converts from a `Duration`, counted from the moment of conversion, and from an `Instant`,
+/// so generic code can take `impl Into<Deadline>` and hand it to `Parker::park_until`.
+///
+/// `park_until` counts a `Duration` from the start of the park instead, on the parker's clock,
+/// the same as `park_timeout` does. Deadlines compare by their instant alone.
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    instant: Option<Instant>,
+    /// The `Duration` the deadline was made from, for `park_until` to count from the park
+    after: Option<Duration>
+}
+
+impl Deadline {
+    /// A deadline that never comes
+    pub const NEVER: Deadline = Deadline { instant: None, after: None };
+
+    /// The deadline `instant`
+    pub fn at(instant: Instant) -> Deadline {
+        Deadline { instant: Some(instant), after: None }
+    }
+
+    /// The deadline `duration` from now
+    ///
+    /// A `duration` too long for an `Instant` to hold, such as `Duration::MAX`, never comes.
+    pub fn after(duration: Duration) -> Deadline {
+        Deadline { instant: Instant::now().checked_add(duration), after: Some(duration) }
+    }
+
+    /// Return the instant of the deadline, or `None` if it never comes
+    pub fn instant(&self) -> Option<Instant> {
+        self.instant
+    }
+
+    /// Return the duration the deadline was made from by `Deadline::after`
+    pub(crate) fn duration(&self) -> Option<Duration> {
+        self.after
+    }
+
+    /// Return the time left until the deadline, zero once it has passed, or `None` if it never
+    /// comes
+    pub fn remaining(&self) -> Option<Duration> {
+        self.instant.map(|instant| instant.saturating_duration_since(Instant::now()))
+    }
+
+    /// Return `true` if the deadline has passed
+    pub fn has_passed(&self) -> bool {
+        self.instant.is_some_and(|instant| instant <= Instant::now())
+    }
+}
+
+impl PartialEq for Deadline {
+    fn eq(&self, other: &Deadline) -> bool {
+        self.instant == other.instant
+    }
+}
+
+impl Eq for Deadline {}
+
+impl std::hash::Hash for Deadline {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.instant.hash(state)
+    }
+}
+
+// `None` sorts first for `Option`, but a deadline that never comes is the latest of all
+impl PartialOrd for Deadline {
+    fn partial_cmp(&self, other: &Deadline) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Deadline {
+    fn cmp(&self, other: &Deadline) -> std::cmp::Ordering {
+        match (self.instant, other.instant) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal
+        }
+    }
+}
+
+impl From<Instant> for Deadline {
+    fn from(instant: Instant) -> Deadline {
+        Deadline::at(instant)
+    }
+}
+
+impl From<Duration> for Deadline {
+    fn from(duration: Duration) -> Deadline {
+        Deadline::after(duration)
+    }
+}
+
+impl From<Option<Instant>> for Deadline {
+    fn from(instant: Option<Instant>) -> Deadline {
+        Deadline { instant, after: None }
+    }
+}
+
+/// How `Parker::park_until` returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[must_use = "a park may also end because its deadline passed"]
+pub enum ParkResult {
+    /// A notification was consumed
+    Notified,
+    /// The deadline passed without a notification
+    TimedOut
+}
+
+impl ParkResult {
+    /// return `true` if the park ended because of a notification
+    pub fn is_notified(&self) -> bool {
+        *self == ParkResult::Notified
+    }
+
+    /// return `true` if the park ended because its deadline passed
+    pub fn is_timed_out(&self) -> bool {
+        *self == ParkResult::TimedOut
+    }
+}
@@ -0,0 +1,69 @@
+use std::fmt::Formatter;
+#[cfg(feature = "diagnostics")]
+use std::time::Instant;
+
+use crate::state::{EMPTY, NOTIFIED, PARKED};
+
+/// What a parker was up to when `Parker::debug_state` or `Unparker::debug_state` looked
+///
+/// The fields are read one at a time without stopping the parker, so with threads parking and
+/// unparking meanwhile they may not all agree. From a hung process they are just what's there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DebugState {
+    /// Identifier of the parker, see `Parker::id`
+    pub parker_id: usize,
+    /// The raw state word: 0 with nothing going on, 1 while a thread parks, 2 with a
+    /// notification waiting for the next park
+    pub state: u32,
+    /// `true` while a thread is in a park, blocked or about to block
+    pub blocked: bool,
+    /// `true` with a notification waiting for the next park
+    pub notified: bool,
+    /// Number of live `Unparker` handles, see `Parker::handle_count`
+    pub handle_count: usize,
+    /// `false` once the `Parker` was dropped, leaving nothing to wake
+    pub parker_alive: bool,
+    /// Number of notifications consumed, see `Parker::generation`
+    pub generation: usize,
+    /// Wake reasons set by `Unparker::unpark_flags` and not yet taken by `Parker::park_flags`
+    pub flags: usize,
+    /// When the parker was last unparked, or `None` if it never was
+    #[cfg(feature = "diagnostics")]
+    pub last_unpark: Option<Instant>
+}
+
+impl DebugState {
+
+    /// Writes the snapshot as the fields of a struct by the name of `name`
+    pub(crate) fn fmt_as(&self, name: &str, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct(name);
+        s.field("id", &self.parker_id)
+            .field("state", &StateName(self.state))
+            .field("handle_count", &self.handle_count)
+            .field("generation", &self.generation);
+        if self.flags != 0 {
+            s.field("flags", &format_args!("{:#x}", self.flags));
+        }
+        if !self.parker_alive {
+            s.field("parker_alive", &false);
+        }
+        #[cfg(feature = "diagnostics")]
+        s.field("last_unpark_ago", &self.last_unpark.map(|at| at.elapsed()));
+        s.finish()
+    }
+}
+
+/// Shows a state word by the name of its value
+struct StateName(u32);
+
+impl std::fmt::Debug for StateName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            EMPTY => f.pad("Empty"),
+            PARKED => f.pad("Parked"),
+            NOTIFIED => f.pad("Notified"),
+            state => write!(f, "{}", state)
+        }
+    }
+}
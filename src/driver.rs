@@ -0,0 +1,86 @@
+//! Traits for drivers that block the thread, so that one can wrap another
+//!
+//! A timer driver, say, implements `Park` on top of any inner `Park`: it fires expired timers,
+//! then parks the inner driver until the next deadline at most. An I/O driver can sit on top of
+//! that in turn, and `Parker` is at the bottom of the stack.
+//!
+//! ```ignore
+//! struct Timer<P: Park> {
+//!     inner: P,
+//!     timers: BinaryHeap<Reverse<(Instant, Waker)>>
+//! }
+//!
+//! impl<P: Park> Park for Timer<P> {
+//!     type Unpark = P::Unpark;
+//!
+//!     fn park(&mut self) {
+//!         match self.next_deadline() {
+//!             Some(deadline) => self.inner.park_timeout(deadline.saturating_duration_since(Instant::now())),
+//!             None => self.inner.park()
+//!         }
+//!         self.fire_expired();
+//!     }
+//!
+//!     // ...
+//! }
+//! ```
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{Parker, Unparker};
+
+/// Blocks the current thread until woken through its `Unpark` handle
+pub trait Park {
+    /// Handle that wakes this driver from other threads
+    type Unpark: Unpark;
+
+    /// Blocks until woken. May return spuriously.
+    fn park(&mut self);
+
+    /// Blocks until woken, or for at most `duration`. May return spuriously.
+    fn park_timeout(&mut self, duration: Duration);
+
+    /// Return a handle for waking this driver
+    fn unpark_handle(&self) -> Self::Unpark;
+}
+
+/// Wakes a `Park` driver
+pub trait Unpark: Send + Sync + 'static {
+    /// Wakes the driver, or makes its next park return at once if it isn't parked
+    fn unpark(&self);
+}
+
+impl Park for Parker {
+    type Unpark = Unparker;
+
+    fn park(&mut self) {
+        Parker::park(self);
+    }
+
+    fn park_timeout(&mut self, duration: Duration) {
+        Parker::park_timeout(self, duration);
+    }
+
+    fn unpark_handle(&self) -> Unparker {
+        self.unparker()
+    }
+}
+
+impl Unpark for Unparker {
+    fn unpark(&self) {
+        Unparker::unpark(self);
+    }
+}
+
+impl<T: Unpark + ?Sized> Unpark for Box<T> {
+    fn unpark(&self) {
+        (**self).unpark();
+    }
+}
+
+impl<T: Unpark + ?Sized> Unpark for Arc<T> {
+    fn unpark(&self) {
+        (**self).unpark();
+    }
+}
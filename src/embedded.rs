@@ -0,0 +1,136 @@
+//! A parker for bare-metal targets whose `unpark` may be called from an interrupt handler
+//!
+//! The state lives behind a `critical_section::Mutex`, so it works without atomic
+//! compare-and-swap and without `std`. There are no timed parks, as there is no portable clock.
+//!
+//! ```ignore
+//! static PARKER: parking::embedded::Parker = parking::embedded::Parker::new();
+//!
+//! #[interrupt]
+//! fn UART0() {
+//!     PARKER.unpark();
+//! }
+//!
+//! fn main() -> ! {
+//!     loop {
+//!         PARKER.park();
+//!         // handle what the interrupt left for us
+//!     }
+//! }
+//! ```
+
+use core::cell::Cell;
+use core::fmt::Formatter;
+
+use critical_section::Mutex;
+
+/// Waits for a notification, sleeping through the wait hook in between checks
+///
+/// Meant to be parked on by a single execution context, typically the main loop.
+pub struct Parker {
+    notified: Mutex<Cell<bool>>,
+    wait: fn(),
+    wake: fn()
+}
+
+impl Parker {
+
+    /// Creates a parker that sleeps with `WFE` and wakes with `SEV` on bare-metal ARM, and spins
+    /// everywhere else
+    pub const fn new() -> Parker {
+        Parker::with_hooks(wait_for_event, send_event)
+    }
+
+    /// Creates a parker that calls `wait` to sleep between checks and `wake` after every unpark
+    ///
+    /// `wait` runs outside the critical section. It must return if a wakeup arrived after the
+    /// parker last checked for one, like `WFE` does thanks to the event register, or that wakeup
+    /// is missed until the next one.
+    pub const fn with_hooks(wait: fn(), wake: fn()) -> Parker {
+        Parker {
+            notified: Mutex::new(Cell::new(false)),
+            wait,
+            wake
+        }
+    }
+
+    /// Blocks until notified and then goes back into unnotified state
+    pub fn park(&self) {
+        while !self.try_park() {
+            (self.wait)();
+        }
+    }
+
+    /// Consumes a notification without blocking
+    ///
+    /// return `true` if the parker was notified
+    pub fn try_park(&self) -> bool {
+        critical_section::with(|cs| self.notified.borrow(cs).replace(false))
+    }
+
+    /// Notifies the parker
+    ///
+    /// return `true` if this call is the first to notify the parker, or `false`
+    /// if the parker was already notified
+    pub fn unpark(&self) -> bool {
+        let first = critical_section::with(|cs| !self.notified.borrow(cs).replace(true));
+        (self.wake)();
+        first
+    }
+
+    /// Return a handle for unparking
+    pub fn unparker(&self) -> Unparker<'_> {
+        Unparker { parker: self }
+    }
+}
+
+impl Default for Parker {
+    fn default() -> Self {
+        Parker::new()
+    }
+}
+
+impl core::fmt::Debug for Parker {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.pad("Parker { .. }")
+    }
+}
+
+/// Notifies a parker
+#[derive(Clone, Copy)]
+pub struct Unparker<'a> {
+    parker: &'a Parker
+}
+
+impl Unparker<'_> {
+    /// Notifies the parker, see `Parker::unpark`
+    pub fn unpark(&self) -> bool {
+        self.parker.unpark()
+    }
+}
+
+impl core::fmt::Debug for Unparker<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.pad("Unparker { .. }")
+    }
+}
+
+#[cfg(all(any(target_arch = "arm", target_arch = "aarch64"), target_os = "none"))]
+fn wait_for_event() {
+    // SAFETY: `wfe` only suspends execution until the next event or interrupt
+    unsafe { core::arch::asm!("wfe", options(nomem, nostack, preserves_flags)) }
+}
+
+#[cfg(all(any(target_arch = "arm", target_arch = "aarch64"), target_os = "none"))]
+fn send_event() {
+    // SAFETY: `sev` only signals an event to every core
+    unsafe { core::arch::asm!("sev", options(nomem, nostack, preserves_flags)) }
+}
+
+#[cfg(not(all(any(target_arch = "arm", target_arch = "aarch64"), target_os = "none")))]
+fn wait_for_event() {
+    core::hint::spin_loop();
+}
+
+#[cfg(not(all(any(target_arch = "arm", target_arch = "aarch64"), target_os = "none")))]
+fn send_event() {}
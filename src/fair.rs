@@ -0,0 +1,192 @@
+use std::cell::UnsafeCell;
+use std::collections::VecDeque;
+use std::fmt::Formatter;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::atomic::AtomicBool;
+use crate::{Parker, Unparker};
+
+thread_local! {
+    /// Parks the thread in `FairMutex::lock`, shared by every mutex the thread locks
+    static PARKER: Parker = Parker::new();
+}
+
+/// A mutex that hands the lock to the thread that has been waiting longest
+///
+/// Unlocking with threads waiting passes the lock straight to the first of them, without a
+/// window for another thread to take it in between, so a thread waits for at most the critical
+/// sections of the threads queued before it. That costs throughput under contention, as every
+/// handoff waits for the next owner to wake up.
+///
+/// A panic while holding the lock doesn't poison it.
+#[derive(Default)]
+pub struct FairMutex<T: ?Sized> {
+    queue: Mutex<Queue>,
+    data: UnsafeCell<T>
+}
+
+#[derive(Default)]
+struct Queue {
+    locked: bool,
+    /// Threads waiting for the lock, longest waiting first
+    waiters: VecDeque<Arc<Waiter>>
+}
+
+/// A thread in `FairMutex::lock`
+struct Waiter {
+    /// Set by the unlock that handed this waiter the lock, so stray wakeups of the thread's
+    /// parker don't end the wait
+    granted: AtomicBool,
+    unparker: Unparker
+}
+
+/// Access to the data of a locked `FairMutex`, unlocking it on drop
+#[must_use = "the mutex is unlocked as soon as the guard is dropped"]
+pub struct FairMutexGuard<'a, T: ?Sized> {
+    mutex: &'a FairMutex<T>
+}
+
+// SAFETY: the lock hands out access to `data` to one thread at a time
+unsafe impl<T: ?Sized + Send> Send for FairMutex<T> {}
+unsafe impl<T: ?Sized + Send> Sync for FairMutex<T> {}
+unsafe impl<T: ?Sized + Sync> Sync for FairMutexGuard<'_, T> {}
+
+impl<T> FairMutex<T> {
+
+    pub fn new(data: T) -> FairMutex<T> {
+        FairMutex {
+            queue: Mutex::new(Queue::default()),
+            data: UnsafeCell::new(data)
+        }
+    }
+
+    /// Consumes the mutex, returning the data
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: ?Sized> FairMutex<T> {
+
+    /// Locks the data, parking behind the threads already waiting until it's this thread's turn
+    pub fn lock(&self) -> FairMutexGuard<'_, T> {
+        self.lock_deadline(None).expect("an untimed lock never times out")
+    }
+
+    /// Locks the data, parking until it's this thread's turn or until `duration` has passed
+    ///
+    /// return `None` if the lock didn't come in time
+    pub fn lock_timeout(&self, duration: Duration) -> Option<FairMutexGuard<'_, T>> {
+        self.lock_deadline(Instant::now().checked_add(duration))
+    }
+
+    /// Locks the data if it's unlocked and no thread is waiting for it, without blocking
+    pub fn try_lock(&self) -> Option<FairMutexGuard<'_, T>> {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.locked {
+            return None;
+        }
+        queue.locked = true;
+        Some(FairMutexGuard { mutex: self })
+    }
+
+    /// Return a mutable reference to the data, which needs no locking as the mutex is borrowed
+    /// exclusively
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    fn lock_deadline(&self, deadline: Option<Instant>) -> Option<FairMutexGuard<'_, T>> {
+        PARKER.with(|parker| {
+            let waiter = {
+                let mut queue = self.queue.lock().unwrap();
+                // While threads are queued the lock only moves by handoff, so `locked` stays set
+                if !queue.locked {
+                    queue.locked = true;
+                    return Some(FairMutexGuard { mutex: self });
+                }
+                let waiter = Arc::new(Waiter {
+                    granted: AtomicBool::new(false),
+                    unparker: parker.unparker()
+                });
+                queue.waiters.push_back(waiter.clone());
+                waiter
+            };
+
+            loop {
+                if waiter.granted.load(SeqCst) {
+                    return Some(FairMutexGuard { mutex: self });
+                }
+                match deadline {
+                    None => parker.park(),
+                    Some(deadline) => {
+                        if !parker.park_deadline(deadline) && Instant::now() >= deadline {
+                            let mut queue = self.queue.lock().unwrap();
+                            match queue.waiters.iter().position(|w| Arc::ptr_eq(w, &waiter)) {
+                                Some(i) => {
+                                    queue.waiters.remove(i);
+                                    return None;
+                                }
+                                // An unlock handed us the lock while timing out
+                                None => return Some(FairMutexGuard { mutex: self })
+                            }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Hands the lock to the first waiter, or unlocks it if there is none
+    fn unlock(&self) {
+        let next = {
+            let mut queue = self.queue.lock().unwrap();
+            let next = queue.waiters.pop_front();
+            if next.is_none() {
+                queue.locked = false;
+            }
+            next
+        };
+        if let Some(next) = next {
+            next.granted.store(true, SeqCst);
+            next.unparker.unpark();
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for FairMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the guard holds the lock
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for FairMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: the guard holds the lock
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for FairMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.mutex.unlock();
+    }
+}
+
+impl<T: ?Sized> std::fmt::Debug for FairMutex<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("FairMutex { .. }")
+    }
+}
+
+impl<T: ?Sized + std::fmt::Debug> std::fmt::Debug for FairMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&**self, f)
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::Arc;
+use std::sync::atomic::Ordering::Relaxed;
+use std::task::{Wake, Waker};
+use std::thread::Thread;
+
+use crate::parker::{Handle, Inner, NEXT_ID};
+use crate::Unparker;
+
+/// Target of an `Unparker` that doesn't belong to a `Parker`
+pub(crate) struct Foreign {
+    pub(crate) id: usize,
+    target: Target
+}
+
+enum Target {
+    Waker(Waker),
+    Thread(Thread),
+    /// Notifies `Unparker` first, then wakes the poll loop
+    #[cfg(feature = "mio")]
+    Mio(Unparker, Arc<mio::Waker>)
+}
+
+impl Foreign {
+
+    fn new(target: Target) -> Arc<Foreign> {
+        Arc::new(Foreign {
+            id: NEXT_ID.fetch_add(1, Relaxed),
+            target
+        })
+    }
+
+    pub(crate) fn wake(&self) -> bool {
+        match &self.target {
+            Target::Waker(waker) => waker.wake_by_ref(),
+            Target::Thread(thread) => thread.unpark(),
+            #[cfg(feature = "mio")]
+            Target::Mio(unparker, waker) => {
+                let first = unparker.unpark();
+                // Failing to wake means the poll is gone or its wakeup counter is saturated, in
+                // which case a wakeup is already pending
+                let _ = waker.wake();
+                return first;
+            }
+        }
+        true
+    }
+
+    /// Return the parker this target notifies, if it has one
+    pub(crate) fn parker(&self) -> Option<&Arc<Inner>> {
+        #[cfg(feature = "mio")]
+        if let Target::Mio(unparker, _) = &self.target {
+            return unparker.parker();
+        }
+        None
+    }
+
+    /// Like `wake`, but also sets `flags` on a parker, see `Unparker::unpark_flags`
+    pub(crate) fn unpark_flags(&self, flags: usize) -> bool {
+        #[cfg(feature = "mio")]
+        if let Target::Mio(unparker, waker) = &self.target {
+            let first = unparker.unpark_flags(flags);
+            let _ = waker.wake();
+            return first;
+        }
+        let _ = flags;
+        self.wake()
+    }
+
+    /// Like `wake`, but only wakes a parker blocked right now, see `Unparker::unpark_now`
+    pub(crate) fn unpark_now(&self) -> bool {
+        #[cfg(feature = "mio")]
+        if let Target::Mio(unparker, waker) = &self.target {
+            let woken = unparker.unpark_now();
+            if woken {
+                let _ = waker.wake();
+            }
+            return woken;
+        }
+        self.wake()
+    }
+}
+
+impl Unparker {
+    /// Creates an unparker that wakes `waker` on every `unpark`
+    ///
+    /// This lets code that accepts an `Unparker` be driven by async tasks as well as threads.
+    pub fn from_waker(waker: Waker) -> Unparker {
+        Unparker {
+            handle: Handle::Foreign(Foreign::new(Target::Waker(waker)))
+        }
+    }
+
+    /// Creates an unparker that calls `Thread::unpark` on every `unpark`
+    ///
+    /// This lets code that accepts an `Unparker` wake threads blocked in `std::thread::park`.
+    pub fn from_thread(thread: Thread) -> Unparker {
+        Unparker {
+            handle: Handle::Foreign(Foreign::new(Target::Thread(thread)))
+        }
+    }
+
+    /// Creates an unparker that notifies this unparker's parker and also wakes `waker`, so one
+    /// handle reaches the thread whether it is parked or blocked in `mio::Poll::poll`
+    ///
+    /// `unpark` on the returned handle reports what this unparker's `unpark` does.
+    #[cfg(feature = "mio")]
+    pub fn with_mio_waker(&self, waker: Arc<mio::Waker>) -> Unparker {
+        Unparker {
+            handle: Handle::Foreign(Foreign::new(Target::Mio(self.clone(), waker)))
+        }
+    }
+}
+
+/// The waker unparks the parker whenever it is woken
+impl From<Unparker> for Waker {
+    fn from(unparker: Unparker) -> Waker {
+        if let Handle::Foreign(foreign) = &unparker.handle {
+            // Hand back the original waker rather than wrapping it a second time
+            if let Target::Waker(waker) = &foreign.target {
+                return waker.clone();
+            }
+        }
+        Waker::from(Arc::new(unparker))
+    }
+}
+
+impl Wake for Unparker {
+    fn wake(self: Arc<Self>) {
+        self.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.unpark();
+    }
+}
@@ -0,0 +1,157 @@
+//! High-resolution timed parks on Windows, see `ParkerBuilder::high_resolution`
+//!
+//! Timeouts of the usual waits are rounded to the scheduler tick, about 15.6 ms unless something
+//! raised the timer resolution. A timed park here waits on an event for the unpark and on a
+//! waitable timer for the deadline instead, created with `CREATE_WAITABLE_TIMER_HIGH_RESOLUTION`
+//! where Windows supports it, which fires within a fraction of a millisecond. Older versions get
+//! a plain waitable timer with the timer resolution raised to 1 ms for the duration of the wait.
+
+use std::convert::TryFrom;
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::atomic::Ordering::SeqCst;
+use std::time::Instant;
+
+use crate::atomic::AtomicU32;
+use crate::state::{self, NOTIFIED};
+use crate::watchdog::StallClock;
+
+type Handle = *mut c_void;
+
+const CREATE_WAITABLE_TIMER_HIGH_RESOLUTION: u32 = 0x0000_0002;
+const TIMER_ALL_ACCESS: u32 = 0x001F_0003;
+const INFINITE: u32 = 0xFFFF_FFFF;
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn CreateEventW(attributes: *const c_void, manual_reset: i32, initial_state: i32, name: *const u16) -> Handle;
+    fn SetEvent(event: Handle) -> i32;
+    fn CreateWaitableTimerExW(attributes: *const c_void, name: *const u16, flags: u32, access: u32) -> Handle;
+    fn SetWaitableTimer(
+        timer: Handle,
+        due_time: *const i64,
+        period: i32,
+        completion: *const c_void,
+        argument: *const c_void,
+        resume: i32
+    ) -> i32;
+    fn WaitForMultipleObjects(count: u32, handles: *const Handle, wait_all: i32, milliseconds: u32) -> u32;
+    fn CloseHandle(handle: Handle) -> i32;
+}
+
+#[link(name = "winmm")]
+extern "system" {
+    fn timeBeginPeriod(period: u32) -> u32;
+    fn timeEndPeriod(period: u32) -> u32;
+}
+
+/// The event an unpark sets and the timer a timed park waits on alongside it
+pub(crate) struct HighResTimer {
+    /// Auto-reset, so each set wakes one wait
+    event: Handle,
+    timer: Handle,
+    /// Set when the timer lacks `CREATE_WAITABLE_TIMER_HIGH_RESOLUTION`, so waits raise the
+    /// timer resolution instead
+    coarse: bool
+}
+
+// SAFETY: event and timer handles are usable from any thread, and only closed on drop
+unsafe impl Send for HighResTimer {}
+unsafe impl Sync for HighResTimer {}
+
+impl HighResTimer {
+
+    /// Return `None` if the handles can't be created, leaving timed parks to the backend
+    pub(crate) fn new() -> Option<HighResTimer> {
+        // SAFETY: creates an unnamed auto-reset event, failing with a null handle
+        let event = unsafe { CreateEventW(ptr::null(), 0, 0, ptr::null()) };
+        if event.is_null() {
+            return None;
+        }
+        let mut coarse = false;
+        // SAFETY: creates unnamed timers, failing with a null handle. Windows before 10 1803
+        // rejects the high resolution flag.
+        let mut timer = unsafe {
+            CreateWaitableTimerExW(ptr::null(), ptr::null(), CREATE_WAITABLE_TIMER_HIGH_RESOLUTION, TIMER_ALL_ACCESS)
+        };
+        if timer.is_null() {
+            coarse = true;
+            timer = unsafe { CreateWaitableTimerExW(ptr::null(), ptr::null(), 0, TIMER_ALL_ACCESS) };
+        }
+        if timer.is_null() {
+            // SAFETY: `event` is a handle from `CreateEventW`, closed once
+            unsafe { CloseHandle(event) };
+            return None;
+        }
+        Some(HighResTimer { event, timer, coarse })
+    }
+
+    /// Blocks while `state` is `PARKED` and `deadline` hasn't passed, reporting to the watchdog
+    /// each time the park has been stalled for another threshold
+    ///
+    /// return `true` if notified
+    pub(crate) fn park(&self, state: &AtomicU32, deadline: Instant, stall: &mut Option<StallClock<'_>>) -> bool {
+        if !state::begin_park(state) {
+            return true;
+        }
+        if self.coarse {
+            // SAFETY: always safe to call, paired with `timeEndPeriod` below
+            unsafe { timeBeginPeriod(1) };
+        }
+        loop {
+            // An unpark sets the event after writing `NOTIFIED`, so one that lands after this
+            // check ends the wait below at once
+            if state.load(SeqCst) == NOTIFIED {
+                break;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            let until = match stall {
+                Some(stall) if now >= stall.next_report() => {
+                    stall.report();
+                    continue;
+                }
+                Some(stall) => deadline.min(stall.next_report()),
+                None => deadline
+            };
+            let remaining = until - now;
+            // Negative due times are relative, in 100 ns units
+            let due = -i64::try_from(remaining.as_nanos().div_ceil(100)).unwrap_or(i64::MAX);
+            let handles = [self.event, self.timer];
+            // SAFETY: both handles are open, and `due` outlives the call. Every outcome, a
+            // stale event from an earlier park included, sends us back to check `state`.
+            unsafe {
+                if SetWaitableTimer(self.timer, &due, 0, ptr::null(), ptr::null(), 0) != 0 {
+                    WaitForMultipleObjects(2, handles.as_ptr(), 0, INFINITE);
+                } else {
+                    // Without the timer, fall back to the event's own, coarser timeout
+                    let ms = u32::try_from(remaining.as_millis() + 1).unwrap_or(INFINITE - 1).min(INFINITE - 1);
+                    WaitForMultipleObjects(1, handles.as_ptr(), 0, ms);
+                }
+            }
+        }
+        if self.coarse {
+            // SAFETY: pairs with the `timeBeginPeriod` above
+            unsafe { timeEndPeriod(1) };
+        }
+        state::end_park(state)
+    }
+
+    /// Wakes a park waiting here, after `NOTIFIED` was written to its state
+    pub(crate) fn unpark(&self) {
+        // SAFETY: `event` is an open event handle
+        unsafe { SetEvent(self.event) };
+    }
+}
+
+impl Drop for HighResTimer {
+    fn drop(&mut self) {
+        // SAFETY: both are handles this owns, closed once
+        unsafe {
+            CloseHandle(self.event);
+            CloseHandle(self.timer);
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use std::fmt::Formatter;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Callback registered with `ParkerBuilder::park_hook`
+#[derive(Clone)]
+pub(crate) struct ParkHook(pub(crate) Arc<dyn Fn(Option<Duration>) + Send + Sync>);
+
+impl std::fmt::Debug for ParkHook {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("ParkHook { .. }")
+    }
+}
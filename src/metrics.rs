@@ -0,0 +1,95 @@
+use std::sync::atomic::Ordering::Relaxed;
+
+use crate::atomic::AtomicU64;
+use crate::pad::CachePadded;
+
+/// Counters shared by every parker in the process
+static GLOBAL: Counters = Counters::new();
+
+/// Return a snapshot of the counters summed over every parker in the process
+pub fn global_metrics() -> Metrics {
+    GLOBAL.snapshot()
+}
+
+/// Snapshot of parking activity counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Metrics {
+    /// Calls to any of the park methods
+    pub parks: u64,
+    /// Parks that returned because of a notification
+    pub notified: u64,
+    /// Parks that returned because their timeout elapsed
+    pub timeouts: u64,
+    /// Unparks that found no parked thread, so only had to update the state
+    pub fast_unparks: u64,
+    /// Unparks that had to wake a parked thread
+    pub slow_unparks: u64
+}
+
+/// Split by who writes them, the parking thread or its unparkers, onto cache lines of their own
+pub(crate) struct Counters {
+    parks: CachePadded<ParkCounters>,
+    unparks: CachePadded<UnparkCounters>
+}
+
+struct ParkCounters {
+    parks: AtomicU64,
+    notified: AtomicU64,
+    timeouts: AtomicU64
+}
+
+struct UnparkCounters {
+    fast_unparks: AtomicU64,
+    slow_unparks: AtomicU64
+}
+
+impl Counters {
+
+    pub(crate) const fn new() -> Counters {
+        Counters {
+            parks: CachePadded::new(ParkCounters {
+                parks: AtomicU64::new(0),
+                notified: AtomicU64::new(0),
+                timeouts: AtomicU64::new(0)
+            }),
+            unparks: CachePadded::new(UnparkCounters {
+                fast_unparks: AtomicU64::new(0),
+                slow_unparks: AtomicU64::new(0)
+            })
+        }
+    }
+
+    /// Records a park on this parker and in the global counters
+    pub(crate) fn record_park(&self, notified: bool) {
+        for counters in [self, &GLOBAL] {
+            let parks = &counters.parks;
+            parks.parks.fetch_add(1, Relaxed);
+            if notified {
+                parks.notified.fetch_add(1, Relaxed);
+            } else {
+                parks.timeouts.fetch_add(1, Relaxed);
+            }
+        }
+    }
+
+    /// Records an unpark on this parker and in the global counters
+    pub(crate) fn record_unpark(&self, slow: bool) {
+        for counters in [self, &GLOBAL] {
+            if slow {
+                counters.unparks.slow_unparks.fetch_add(1, Relaxed);
+            } else {
+                counters.unparks.fast_unparks.fetch_add(1, Relaxed);
+            }
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> Metrics {
+        Metrics {
+            parks: self.parks.parks.load(Relaxed),
+            notified: self.parks.notified.load(Relaxed),
+            timeouts: self.parks.timeouts.load(Relaxed),
+            fast_unparks: self.unparks.fast_unparks.load(Relaxed),
+            slow_unparks: self.unparks.slow_unparks.load(Relaxed)
+        }
+    }
+}
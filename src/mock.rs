@@ -0,0 +1,111 @@
+use std::fmt::Formatter;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::atomic::AtomicU32;
+use crate::state::{self, NOTIFIED};
+use crate::watchdog::StallClock;
+
+/// A clock that only moves when told to, for testing code around timed parks without sleeping
+///
+/// Parkers built with `ParkerBuilder::mock_clock` measure their timeouts on this clock: a timed
+/// park returns once it's unparked, or once `advance` has moved the clock past its deadline,
+/// however much real time that takes. Clones share the same time.
+///
+/// `park_timeout` and the like count from `now`, while a deadline passed as an `Instant` is
+/// compared against `now` as is, so compute deadlines with `now` rather than `Instant::now`.
+#[derive(Clone)]
+pub struct MockClock {
+    shared: Arc<Shared>
+}
+
+struct Shared {
+    /// What `now` returned before the first `advance`
+    start: Instant,
+    elapsed: Mutex<Duration>,
+    /// Signalled on every `advance`, and on every unpark of a parker waiting here
+    changed: Condvar
+}
+
+impl MockClock {
+
+    pub fn new() -> MockClock {
+        MockClock {
+            shared: Arc::new(Shared {
+                start: Instant::now(),
+                elapsed: Mutex::new(Duration::from_millis(0)),
+                changed: Condvar::new()
+            })
+        }
+    }
+
+    /// Return the current time on this clock
+    pub fn now(&self) -> Instant {
+        self.shared.start + self.elapsed()
+    }
+
+    /// Return how far the clock has been advanced
+    pub fn elapsed(&self) -> Duration {
+        *self.shared.elapsed.lock().unwrap()
+    }
+
+    /// Moves the clock forward by `duration`, timing out every park whose deadline it passes
+    pub fn advance(&self, duration: Duration) {
+        *self.shared.elapsed.lock().unwrap() += duration;
+        self.shared.changed.notify_all();
+    }
+
+    /// Blocks while `state` is `PARKED` and the clock is before `deadline`, reporting to the
+    /// watchdog each time the park has been stalled for another threshold
+    ///
+    /// The watchdog counts real time, as a park stuck on a clock nobody advances is stalled too.
+    ///
+    /// return `true` if notified
+    pub(crate) fn park(&self, state: &AtomicU32, deadline: Instant, stall: &mut Option<StallClock<'_>>) -> bool {
+        if !state::begin_park(state) {
+            return true;
+        }
+        let shared = &*self.shared;
+        let mut elapsed = shared.elapsed.lock().unwrap();
+        while state.load(SeqCst) != NOTIFIED && shared.start + *elapsed < deadline {
+            elapsed = match stall {
+                None => shared.changed.wait(elapsed).unwrap(),
+                Some(stall) => {
+                    let timeout = stall.next_report().saturating_duration_since(Instant::now());
+                    let (guard, result) = shared.changed.wait_timeout(elapsed, timeout).unwrap();
+                    if result.timed_out() {
+                        // Release the lock while the callback runs, which may unpark or advance
+                        drop(guard);
+                        stall.report();
+                        shared.elapsed.lock().unwrap()
+                    } else {
+                        guard
+                    }
+                }
+            };
+        }
+        drop(elapsed);
+        state::end_park(state)
+    }
+
+    /// Wakes parks waiting on this clock, so they see a notification written to their state
+    ///
+    /// Taking the lock orders this after a park's check of its state, or before it.
+    pub(crate) fn unpark(&self) {
+        let _elapsed = self.shared.elapsed.lock().unwrap();
+        self.shared.changed.notify_all();
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        MockClock::new()
+    }
+}
+
+impl std::fmt::Debug for MockClock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockClock").field("elapsed", &self.elapsed()).finish()
+    }
+}
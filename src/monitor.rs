@@ -0,0 +1,197 @@
+use std::collections::VecDeque;
+use std::fmt::Formatter;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::atomic::AtomicBool;
+use crate::{Parker, Unparker};
+
+thread_local! {
+    /// Parks the thread in `MonitorGuard::wait`, shared by every monitor the thread waits on
+    static PARKER: Parker = Parker::new();
+}
+
+/// Data behind a lock, with waiting for a change to it built in
+///
+/// Like a Java monitor: `lock` the data, and `wait` on the guard to release the lock and park
+/// until `notify_one` or `notify_all`, getting the lock back before `wait` returns. Waiters are
+/// woken in the order they started waiting.
+///
+/// A notification only wakes threads already waiting, so check the condition you wait for under
+/// the lock, as `wait_while` does, rather than waiting unconditionally.
+#[derive(Default)]
+pub struct Monitor<T> {
+    data: Mutex<T>,
+    waiters: Mutex<VecDeque<Arc<Waiter>>>
+}
+
+/// A thread in `MonitorGuard::wait`
+struct Waiter {
+    /// Set by the notification that picked this waiter, so stray wakeups of the thread's
+    /// parker don't end the wait
+    notified: AtomicBool,
+    unparker: Unparker
+}
+
+/// Access to the data of a locked `Monitor`, released on drop
+#[must_use = "the monitor is unlocked as soon as the guard is dropped"]
+pub struct MonitorGuard<'a, T> {
+    monitor: &'a Monitor<T>,
+    guard: MutexGuard<'a, T>
+}
+
+impl<T> Monitor<T> {
+
+    pub fn new(data: T) -> Monitor<T> {
+        Monitor {
+            data: Mutex::new(data),
+            waiters: Mutex::new(VecDeque::new())
+        }
+    }
+
+    /// Locks the data, blocking until it's available
+    pub fn lock(&self) -> MonitorGuard<'_, T> {
+        MonitorGuard {
+            monitor: self,
+            guard: self.data.lock().unwrap()
+        }
+    }
+
+    /// Wakes the thread that has been waiting longest
+    ///
+    /// return `true` if a thread was waiting
+    pub fn notify_one(&self) -> bool {
+        let waiter = self.waiters.lock().unwrap().pop_front();
+        match waiter {
+            Some(waiter) => {
+                waiter.notified.store(true, SeqCst);
+                waiter.unparker.unpark();
+                true
+            }
+            None => false
+        }
+    }
+
+    /// Wakes every waiting thread
+    ///
+    /// return the number of threads woken
+    pub fn notify_all(&self) -> usize {
+        let waiters = std::mem::take(&mut *self.waiters.lock().unwrap());
+        for waiter in &waiters {
+            waiter.notified.store(true, SeqCst);
+            waiter.unparker.unpark();
+        }
+        waiters.len()
+    }
+
+    /// Return a mutable reference to the data, which needs no locking as the monitor is borrowed
+    /// exclusively
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut().unwrap()
+    }
+
+    /// Consumes the monitor, returning the data
+    pub fn into_inner(self) -> T {
+        self.data.into_inner().unwrap()
+    }
+}
+
+impl<'a, T> MonitorGuard<'a, T> {
+
+    /// Releases the lock and parks until notified, then locks the data again
+    pub fn wait(self) -> MonitorGuard<'a, T> {
+        self.wait_deadline(None).0
+    }
+
+    /// Releases the lock and parks until notified or until `duration` has passed, then locks the
+    /// data again
+    ///
+    /// return the guard and `true` if notified before the timeout
+    pub fn wait_timeout(self, duration: Duration) -> (MonitorGuard<'a, T>, bool) {
+        self.wait_deadline(Instant::now().checked_add(duration))
+    }
+
+    /// Waits until `condition` returns `false`, checking it under the lock before every wait
+    pub fn wait_while<F>(mut self, mut condition: F) -> MonitorGuard<'a, T>
+        where F: FnMut(&mut T) -> bool
+    {
+        while condition(&mut self.guard) {
+            self = self.wait();
+        }
+        self
+    }
+
+    fn wait_deadline(self, deadline: Option<Instant>) -> (MonitorGuard<'a, T>, bool) {
+        let monitor = self.monitor;
+        let notified = PARKER.with(|parker| {
+            let waiter = Arc::new(Waiter {
+                notified: AtomicBool::new(false),
+                unparker: parker.unparker()
+            });
+            // Queued before the lock goes, so a notification sent once it's released finds us
+            monitor.waiters.lock().unwrap().push_back(waiter.clone());
+            drop(self.guard);
+
+            loop {
+                if waiter.notified.load(SeqCst) {
+                    return true;
+                }
+                match deadline {
+                    None => parker.park(),
+                    Some(deadline) => {
+                        if !parker.park_deadline(deadline) && Instant::now() >= deadline {
+                            let mut waiters = monitor.waiters.lock().unwrap();
+                            match waiters.iter().position(|w| Arc::ptr_eq(w, &waiter)) {
+                                Some(i) => {
+                                    waiters.remove(i);
+                                    return false;
+                                }
+                                // A notification picked us while timing out
+                                None => return true
+                            }
+                        }
+                    }
+                }
+            }
+        });
+        (monitor.lock(), notified)
+    }
+
+    /// Wakes the thread that has been waiting longest, see `Monitor::notify_one`
+    pub fn notify_one(&self) -> bool {
+        self.monitor.notify_one()
+    }
+
+    /// Wakes every waiting thread, see `Monitor::notify_all`
+    pub fn notify_all(&self) -> usize {
+        self.monitor.notify_all()
+    }
+}
+
+impl<T> Deref for MonitorGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for MonitorGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T> std::fmt::Debug for Monitor<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("Monitor { .. }")
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for MonitorGuard<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&*self.guard, f)
+    }
+}
@@ -0,0 +1,359 @@
+//! Blocking multi-producer, single-consumer channels, where the receiver parks on a `Parker` and
+//! senders unpark it
+//!
+//! `channel` is unbounded and never blocks a sender. `sync_channel` holds at most `bound`
+//! messages, and a sender finding it full parks on a parker of its own until the receiver takes
+//! a message. Every wakeup is an `Unparker::unpark`, and nothing else blocks, short of the lock
+//! around the queue, which is never held across a park.
+//!
+//! The error types are std's, as is the behaviour on disconnection: `recv` drains what is left
+//! once every sender is gone and only then fails, while sends fail at once when the receiver is.
+//!
+//! ```ignore
+//! let (tx, rx) = parking::mpsc::sync_channel(16);
+//! for id in 0..4 {
+//!     let tx = tx.clone();
+//!     thread::spawn(move || tx.send(id).unwrap());
+//! }
+//! drop(tx);
+//! let ids: Vec<_> = rx.iter().collect();
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt::Formatter;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub use std::sync::mpsc::{RecvError, RecvTimeoutError, SendError, TryRecvError, TrySendError};
+
+use crate::{Parker, Unparker};
+
+thread_local! {
+    /// Parks the thread in `SyncSender::send`, shared by every channel the thread sends on
+    static PARKER: Parker = Parker::new();
+}
+
+/// Return an unbounded channel
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let (shared, receiver) = Shared::new(None);
+    (Sender { shared }, receiver)
+}
+
+/// Return a channel holding at most `bound` messages, whose senders block while it's full
+///
+/// Panics if `bound` is zero, as a channel without room can't hand a message over without the
+/// sender waiting for the receiver, which this one doesn't do.
+pub fn sync_channel<T>(bound: usize) -> (SyncSender<T>, Receiver<T>) {
+    assert!(bound > 0, "a sync_channel needs a bound of at least one");
+    let (shared, receiver) = Shared::new(Some(bound));
+    (SyncSender { shared }, receiver)
+}
+
+/// The sending half of a `channel`, which can be cloned for more senders
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>
+}
+
+/// The sending half of a `sync_channel`, which can be cloned for more senders
+pub struct SyncSender<T> {
+    shared: Arc<Shared<T>>
+}
+
+/// The receiving half of a channel
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    parker: Parker
+}
+
+/// An iterator over messages that blocks for each, see `Receiver::iter`
+pub struct Iter<'a, T> {
+    receiver: &'a Receiver<T>
+}
+
+/// An iterator over the messages already sent, see `Receiver::try_iter`
+pub struct TryIter<'a, T> {
+    receiver: &'a Receiver<T>
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    /// Where the receiver parks
+    receiver: Unparker,
+    bound: Option<usize>
+}
+
+struct State<T> {
+    queue: VecDeque<T>,
+    senders: usize,
+    /// Set once the receiver is dropped
+    disconnected: bool,
+    /// Senders parked on a full bounded channel, longest waiting first
+    blocked: VecDeque<Unparker>
+}
+
+impl<T> Shared<T> {
+
+    fn new(bound: Option<usize>) -> (Arc<Shared<T>>, Receiver<T>) {
+        let parker = Parker::new();
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                queue: VecDeque::new(),
+                senders: 1,
+                disconnected: false,
+                blocked: VecDeque::new()
+            }),
+            receiver: parker.unparker(),
+            bound
+        });
+        (shared.clone(), Receiver { shared, parker })
+    }
+
+    fn add_sender(self: &Arc<Self>) -> Arc<Shared<T>> {
+        self.state.lock().unwrap().senders += 1;
+        self.clone()
+    }
+
+    fn drop_sender(&self) {
+        let last = {
+            let mut state = self.state.lock().unwrap();
+            state.senders -= 1;
+            state.senders == 0
+        };
+        // The receiver may be parked waiting for a message that now never comes
+        if last {
+            self.receiver.unpark();
+        }
+    }
+
+    /// Queues `t` if the channel has room, waking the receiver
+    fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.disconnected {
+                return Err(TrySendError::Disconnected(t));
+            }
+            if self.bound.is_some_and(|bound| state.queue.len() >= bound) {
+                return Err(TrySendError::Full(t));
+            }
+            state.queue.push_back(t);
+        }
+        self.receiver.unpark();
+        Ok(())
+    }
+
+    /// Takes the oldest message, waking the sender that has been blocked longest as it makes room
+    fn try_recv(&self) -> Result<T, TryRecvError> {
+        let (t, blocked) = {
+            let mut state = self.state.lock().unwrap();
+            match state.queue.pop_front() {
+                Some(t) => (t, state.blocked.pop_front()),
+                None if state.senders == 0 => return Err(TryRecvError::Disconnected),
+                None => return Err(TryRecvError::Empty)
+            }
+        };
+        if let Some(sender) = blocked {
+            sender.unpark();
+        }
+        Ok(t)
+    }
+}
+
+impl<T> Sender<T> {
+
+    /// Sends `t` without blocking
+    ///
+    /// Fails, handing `t` back, only if the receiver was dropped.
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        self.shared.try_send(t).map_err(|e| match e {
+            TrySendError::Full(t) | TrySendError::Disconnected(t) => SendError(t)
+        })
+    }
+}
+
+impl<T> SyncSender<T> {
+
+    /// Sends `t`, parking while the channel is full
+    ///
+    /// Fails, handing `t` back, if the receiver was dropped, also while waiting for room.
+    pub fn send(&self, mut t: T) -> Result<(), SendError<T>> {
+        PARKER.with(|parker| loop {
+            t = match self.shared.try_send(t) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Disconnected(t)) => return Err(SendError(t)),
+                Err(TrySendError::Full(t)) => t
+            };
+            {
+                let mut state = self.shared.state.lock().unwrap();
+                // Room may have been made, or the receiver dropped, since `try_send` let go of
+                // the lock
+                if state.disconnected || self.shared.bound.is_some_and(|bound| state.queue.len() < bound) {
+                    continue;
+                }
+                state.blocked.push_back(parker.unparker());
+            }
+            parker.park();
+            // A wakeup that came from elsewhere leaves us queued, where the receiver would
+            // waste the wakeup for the next sender on us
+            let mut state = self.shared.state.lock().unwrap();
+            state.blocked.retain(|u| u.id() != parker.id());
+        })
+    }
+
+    /// Sends `t` if the channel has room, without blocking
+    pub fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
+        self.shared.try_send(t)
+    }
+}
+
+impl<T> Receiver<T> {
+
+    /// Takes the oldest message, parking until one is sent
+    ///
+    /// Fails once every sender is gone and no messages are left.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.recv_deadline_opt(None).map_err(|_| RecvError)
+    }
+
+    /// Takes the oldest message, parking until one is sent or until `duration` has passed
+    pub fn recv_timeout(&self, duration: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_deadline_opt(Instant::now().checked_add(duration))
+    }
+
+    /// Takes the oldest message, parking until one is sent or until `deadline`
+    pub fn recv_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+        self.recv_deadline_opt(Some(deadline))
+    }
+
+    /// Takes the oldest message if there is one, without blocking
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.shared.try_recv()
+    }
+
+    /// Return an iterator that blocks for each message, ending once every sender is gone
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { receiver: self }
+    }
+
+    /// Return an iterator over the messages already sent, ending when there are none left
+    pub fn try_iter(&self) -> TryIter<'_, T> {
+        TryIter { receiver: self }
+    }
+
+    fn recv_deadline_opt(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        loop {
+            match self.shared.try_recv() {
+                Ok(t) => return Ok(t),
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryRecvError::Empty) => {}
+            }
+            // Every send unparks after queueing, so a message sent since `try_recv` looked
+            // leaves a notification that ends the park at once
+            match deadline {
+                None => self.parker.park(),
+                Some(deadline) => {
+                    if !self.parker.park_deadline(deadline) && Instant::now() >= deadline {
+                        return self.shared.try_recv().map_err(|e| match e {
+                            TryRecvError::Empty => RecvTimeoutError::Timeout,
+                            TryRecvError::Disconnected => RecvTimeoutError::Disconnected
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Sender { shared: self.shared.add_sender() }
+    }
+}
+
+impl<T> Clone for SyncSender<T> {
+    fn clone(&self) -> Self {
+        SyncSender { shared: self.shared.add_sender() }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.shared.drop_sender();
+    }
+}
+
+impl<T> Drop for SyncSender<T> {
+    fn drop(&mut self) {
+        self.shared.drop_sender();
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let (queue, blocked) = {
+            let mut state = self.shared.state.lock().unwrap();
+            state.disconnected = true;
+            (std::mem::take(&mut state.queue), std::mem::take(&mut state.blocked))
+        };
+        // Messages left over are dropped here rather than under the lock, as dropping them may
+        // run arbitrary code
+        drop(queue);
+        for sender in blocked {
+            sender.unpark();
+        }
+    }
+}
+
+impl<T> Iterator for Iter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl<T> Iterator for TryIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Receiver<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> std::fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("Sender { .. }")
+    }
+}
+
+impl<T> std::fmt::Debug for SyncSender<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("SyncSender { .. }")
+    }
+}
+
+impl<T> std::fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("Receiver { .. }")
+    }
+}
+
+impl<T> std::fmt::Debug for Iter<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("Iter { .. }")
+    }
+}
+
+impl<T> std::fmt::Debug for TryIter<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("TryIter { .. }")
+    }
+}
@@ -0,0 +1,91 @@
+use std::fmt::Formatter;
+use std::sync::RwLock;
+
+use crate::Unparker;
+
+/// Notifies a set of parkers at once
+///
+/// Unparkers are registered with `add` and unregistered with the returned key. Unparking takes a
+/// shared lock, so broadcasts from several threads don't serialize on each other.
+#[derive(Default)]
+pub struct MultiUnparker {
+    slots: RwLock<Slots>
+}
+
+/// Identifies an unparker registered with a `MultiUnparker`
+///
+/// Keys are reused once the unparker they refer to is removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UnparkerKey(usize);
+
+#[derive(Default)]
+struct Slots {
+    entries: Vec<Option<Unparker>>,
+    free: Vec<usize>,
+    len: usize
+}
+
+impl MultiUnparker {
+
+    pub fn new() -> MultiUnparker {
+        MultiUnparker::default()
+    }
+
+    /// Registers `unparker`, returning the key to remove it with
+    pub fn add(&self, unparker: Unparker) -> UnparkerKey {
+        let mut slots = self.slots.write().unwrap();
+        slots.len += 1;
+        match slots.free.pop() {
+            Some(i) => {
+                slots.entries[i] = Some(unparker);
+                UnparkerKey(i)
+            }
+            None => {
+                slots.entries.push(Some(unparker));
+                UnparkerKey(slots.entries.len() - 1)
+            }
+        }
+    }
+
+    /// Unregisters the unparker behind `key`
+    ///
+    /// return the unparker, or `None` if `key` was already removed
+    pub fn remove(&self, key: UnparkerKey) -> Option<Unparker> {
+        let mut slots = self.slots.write().unwrap();
+        let unparker = slots.entries.get_mut(key.0)?.take()?;
+        slots.len -= 1;
+        slots.free.push(key.0);
+        Some(unparker)
+    }
+
+    /// Notifies every registered parker
+    ///
+    /// return `true` if this call was the first to notify at least one of them
+    pub fn unpark(&self) -> bool {
+        self.unpark_all() > 0
+    }
+
+    /// Notifies every registered parker
+    ///
+    /// return the number of parkers this call was the first to notify
+    pub fn unpark_all(&self) -> usize {
+        let slots = self.slots.read().unwrap();
+        slots.entries.iter().flatten().filter(|u| u.unpark()).count()
+    }
+
+    /// Return the number of registered unparkers
+    pub fn len(&self) -> usize {
+        self.slots.read().unwrap().len
+    }
+
+    /// Return `true` if no unparkers are registered
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl std::fmt::Debug for MultiUnparker {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("MultiUnparker { .. }")
+    }
+}
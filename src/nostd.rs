@@ -0,0 +1,213 @@
+//! The `Parker` and `Unparker` API without `std`, for RTOSes, kernels and other targets with an
+//! allocator but no OS threads to block
+//!
+//! The notification state machine is the one behind the std parker. Waiting is up to a `Wait`
+//! hook, such as one taking and giving an RTOS semaphore or task notification, or by default
+//! `Spin`, which polls. There are no timed parks, as there is no portable clock.
+//!
+//! Handles share the parker through an `Arc`, and `alloc::sync` only exists on targets with
+//! native compare-and-swap. Elsewhere, such as on thumbv6m, enable `portable-atomic` as well,
+//! which takes the `Arc` and the atomics from `portable-atomic`, and with it `critical-section`
+//! or another way for `portable-atomic` to provide compare-and-swap.
+//!
+//! ```ignore
+//! struct TaskNotify(TaskHandle);
+//!
+//! impl parking::nostd::Wait for TaskNotify {
+//!     fn wait(&self) {
+//!         rtos::task_notify_take();
+//!     }
+//!
+//!     fn wake(&self) {
+//!         rtos::task_notify_give(self.0);
+//!     }
+//! }
+//!
+//! let parker = parking::nostd::Parker::with_wait(TaskNotify(rtos::current_task()));
+//! let unparker = parker.unparker();
+//! ```
+
+use alloc::boxed::Box;
+#[cfg(not(feature = "portable-atomic"))]
+use alloc::sync::Arc;
+#[cfg(feature = "portable-atomic")]
+use portable_atomic_util::Arc;
+use core::cell::Cell;
+use core::fmt::Formatter;
+use core::hint;
+use core::marker::PhantomData;
+use core::sync::atomic::Ordering::SeqCst;
+
+use crate::atomic::AtomicU32;
+use crate::state::{self, EMPTY, NOTIFIED, PARKED};
+
+/// How a parked thread waits for its unparker
+pub trait Wait: Send + Sync + 'static {
+    /// Sleeps until `wake` is called, or returns at once if it was called since this last
+    /// returned
+    ///
+    /// May also return early or spuriously, the parker checks for its notification before
+    /// waiting again.
+    fn wait(&self);
+
+    /// Wakes the thread in `wait`, or has its next `wait` return at once
+    ///
+    /// Only called when the parked thread is about to wait or waiting.
+    fn wake(&self);
+}
+
+/// Waits by polling, for when there's nothing to block on
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Spin;
+
+impl Wait for Spin {
+    fn wait(&self) {
+        hint::spin_loop();
+    }
+
+    fn wake(&self) {}
+}
+
+/// Waits for a notification through a `Wait` hook
+pub struct Parker {
+    inner: Arc<Inner>,
+    _marker: PhantomData<Cell<()>>
+}
+
+/// Notifies a parker
+#[derive(Clone)]
+pub struct Unparker {
+    inner: Arc<Inner>
+}
+
+struct Inner {
+    state: AtomicU32,
+    wait: Box<dyn Wait>
+}
+
+/// Return a new parker and an unparker for it
+pub fn pair() -> (Parker, Unparker) {
+    let parker = Parker::new();
+    let unparker = parker.unparker();
+    (parker, unparker)
+}
+
+impl Parker {
+
+    /// Creates a parker that spins while it waits
+    pub fn new() -> Parker {
+        Parker::with_wait(Spin)
+    }
+
+    /// Creates a parker that waits through `wait`
+    pub fn with_wait<W: Wait>(wait: W) -> Parker {
+        Parker {
+            inner: Arc::new(Inner {
+                state: AtomicU32::new(EMPTY),
+                wait: Box::new(wait)
+            }),
+            _marker: PhantomData
+        }
+    }
+
+    /// Blocks until notified and then goes back into unnotified state
+    pub fn park(&self) {
+        let inner = &*self.inner;
+        if !state::begin_park(&inner.state) {
+            return;
+        }
+        while inner.state.load(SeqCst) == PARKED {
+            inner.wait.wait();
+        }
+        state::end_park(&inner.state);
+    }
+
+    /// Consumes a notification without blocking
+    ///
+    /// return `true` if the parker was notified
+    pub fn try_park(&self) -> bool {
+        state::try_consume(&self.inner.state)
+    }
+
+    /// Notifies the parker
+    ///
+    /// return `true` if this call is the first to notify the parker, or `false`
+    /// if the parker was already notified
+    pub fn unpark(&self) -> bool {
+        self.inner.unpark()
+    }
+
+    /// Return a handle for unparking
+    pub fn unparker(&self) -> Unparker {
+        Unparker {
+            inner: self.inner.clone()
+        }
+    }
+
+    /// Return the number of live `Unparker` handles for this parker
+    pub fn handle_count(&self) -> usize {
+        Arc::strong_count(&self.inner) - 1
+    }
+
+    /// Converts the parker into a handle for unparking
+    pub fn into_unparker(self) -> Unparker {
+        Unparker {
+            inner: self.inner
+        }
+    }
+}
+
+impl Default for Parker {
+    fn default() -> Self {
+        Parker::new()
+    }
+}
+
+impl core::fmt::Debug for Parker {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.pad("Parker { .. }")
+    }
+}
+
+impl Unparker {
+
+    /// Notifies the parker
+    ///
+    /// return `true` if this call is the first to notify the parker, or `false`
+    /// if the parker was already notified
+    pub fn unpark(&self) -> bool {
+        self.inner.unpark()
+    }
+
+    /// Notifies the parker only if its thread is already waiting, leaving no notification for a
+    /// later park otherwise
+    ///
+    /// return `true` if the thread was woken
+    pub fn unpark_now(&self) -> bool {
+        if !state::notify_parked(&self.inner.state) {
+            return false;
+        }
+        self.inner.wait.wake();
+        true
+    }
+}
+
+impl core::fmt::Debug for Unparker {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.pad("Unparker { .. }")
+    }
+}
+
+impl Inner {
+    fn unpark(&self) -> bool {
+        match state::notify(&self.state) {
+            EMPTY => true,
+            NOTIFIED => false,
+            PARKED => {
+                self.wait.wake();
+                true
+            }
+            _ => panic!("inconsistent state in unpark")
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+static OBSERVER: OnceLock<&'static dyn Observer> = OnceLock::new();
+
+/// Callbacks on every park and unpark in the process, for schedulers keeping their own
+/// run-state accounting and for profilers
+///
+/// Registered once with `set_observer`. Until then parks and unparks only pay for checking
+/// that there's none. The callbacks run on the parking or unparking thread, outside the
+/// parker's locks, and should be quick, as they hold up the thread that does either.
+pub trait Observer: Sync {
+    /// A park of the parker `parker_id` began, to end at `deadline` at the latest
+    fn on_park(&self, parker_id: usize, deadline: Option<Instant>) {
+        let _ = (parker_id, deadline);
+    }
+
+    /// A park of the parker `parker_id` ended after `elapsed`, with `notified` telling a
+    /// notification from a timeout
+    fn on_wakeup(&self, parker_id: usize, notified: bool, elapsed: Duration) {
+        let _ = (parker_id, notified, elapsed);
+    }
+
+    /// The parker `parker_id` was unparked. `first` is whether this unpark delivered the
+    /// notification, `woke` whether it had to wake a parked thread.
+    fn on_unpark(&self, parker_id: usize, first: bool, woke: bool) {
+        let _ = (parker_id, first, woke);
+    }
+}
+
+/// Registers the observer every park and unpark reports to
+///
+/// Returns `observer` back if one was already registered.
+pub fn set_observer(observer: &'static dyn Observer) -> Result<(), &'static dyn Observer> {
+    OBSERVER.set(observer)
+}
+
+#[inline]
+pub(crate) fn get() -> Option<&'static dyn Observer> {
+    OBSERVER.get().copied()
+}
@@ -0,0 +1,158 @@
+//! A channel for a single value: the sender stores it and unparks the receiver, which parks
+//! until it's there
+//!
+//! Dropping the sender without sending wakes the receiver as well, which then fails with
+//! `RecvError`, so a receiver never waits for a value that can't come. The error types are std's.
+//!
+//! ```ignore
+//! let (tx, rx) = parking::oneshot();
+//! thread::spawn(move || tx.send(compute()).unwrap());
+//! let result = rx.recv_timeout(Duration::from_secs(1))?;
+//! ```
+
+use std::fmt::Formatter;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::mpsc::{RecvError, RecvTimeoutError, SendError, TryRecvError};
+use crate::{Parker, Unparker};
+
+/// Return the two ends of a channel for a single value
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let parker = Parker::new();
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            value: None,
+            sender: true,
+            receiver: true
+        }),
+        receiver: parker.unparker()
+    });
+    (Sender { shared: shared.clone() }, Receiver { shared, parker })
+}
+
+/// The sending end of a `oneshot` channel, used up by sending
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>
+}
+
+/// The receiving end of a `oneshot` channel
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    parker: Parker
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    /// Where the receiver parks
+    receiver: Unparker
+}
+
+struct State<T> {
+    /// The value sent and not yet received
+    value: Option<T>,
+    /// Cleared when the sender is dropped, also after sending
+    sender: bool,
+    /// Cleared when the receiver is dropped
+    receiver: bool
+}
+
+impl<T> Sender<T> {
+
+    /// Sends `t`, waking the receiver
+    ///
+    /// Fails, handing `t` back, if the receiver was dropped.
+    pub fn send(self, t: T) -> Result<(), SendError<T>> {
+        let mut state = self.shared.state.lock().unwrap();
+        if !state.receiver {
+            return Err(SendError(t));
+        }
+        state.value = Some(t);
+        // Dropping `self` then unparks the receiver
+        Ok(())
+    }
+
+    /// Return `true` if the receiver was dropped, so that sending would fail
+    pub fn is_closed(&self) -> bool {
+        !self.shared.state.lock().unwrap().receiver
+    }
+}
+
+impl<T> Receiver<T> {
+
+    /// Parks until the value is sent and takes it
+    ///
+    /// Fails if the sender is dropped without sending, or once the value was received.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.recv_deadline(None).map_err(|_| RecvError)
+    }
+
+    /// Parks until the value is sent and takes it, but at most for `duration`
+    pub fn recv_timeout(&self, duration: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_deadline(Instant::now().checked_add(duration))
+    }
+
+    /// Takes the value if it was sent, without blocking
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.value.take() {
+            Some(t) => Ok(t),
+            None if state.sender => Err(TryRecvError::Empty),
+            None => Err(TryRecvError::Disconnected)
+        }
+    }
+
+    fn recv_deadline(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        loop {
+            match self.try_recv() {
+                Ok(t) => return Ok(t),
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryRecvError::Empty) => {}
+            }
+            // The sender unparks after it's done, so anything since `try_recv` looked leaves a
+            // notification that ends the park at once
+            match deadline {
+                None => self.parker.park(),
+                Some(deadline) => {
+                    if !self.parker.park_deadline(deadline) && Instant::now() >= deadline {
+                        return self.try_recv().map_err(|e| match e {
+                            TryRecvError::Empty => RecvTimeoutError::Timeout,
+                            TryRecvError::Disconnected => RecvTimeoutError::Disconnected
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().sender = false;
+        self.shared.receiver.unpark();
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let value = {
+            let mut state = self.shared.state.lock().unwrap();
+            state.receiver = false;
+            state.value.take()
+        };
+        // Dropped outside the lock, as dropping it may run arbitrary code
+        drop(value);
+    }
+}
+
+impl<T> std::fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("Sender { .. }")
+    }
+}
+
+impl<T> std::fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("Receiver { .. }")
+    }
+}
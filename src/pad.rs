@@ -0,0 +1,31 @@
+//! Keeping data that different threads write off each other's cache lines
+
+use std::ops::{Deref, DerefMut};
+
+/// Aligns and pads `T` to a cache line, so that nothing else shares its line
+///
+/// x86-64 fetches lines in adjacent pairs and recent ARM and POWER cores have 128-byte lines, so
+/// those get 128 bytes, everything else 64.
+#[cfg_attr(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "powerpc64"), repr(align(128)))]
+#[cfg_attr(not(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "powerpc64")), repr(align(64)))]
+pub(crate) struct CachePadded<T>(T);
+
+impl<T> CachePadded<T> {
+    pub(crate) const fn new(value: T) -> CachePadded<T> {
+        CachePadded(value)
+    }
+}
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for CachePadded<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
@@ -0,0 +1,1141 @@
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::thread;
+use std::cell::Cell;
+use std::sync::{Mutex, Arc, PoisonError, Weak};
+use std::time::{Duration, Instant};
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+use std::fmt::Formatter;
+
+use crate::atomic::{AtomicBool, AtomicU32, AtomicUsize};
+use crate::backend;
+#[cfg(feature = "debug-checks")]
+use crate::checks::{Owner, Parking};
+#[cfg(windows)]
+use crate::boost::PriorityBoost;
+use crate::clock::{self, Clock, BOOTTIME_SLICE};
+use crate::deadline::{Deadline, ParkResult};
+use crate::debug::DebugState;
+use crate::foreign::Foreign;
+#[cfg(windows)]
+use crate::hires::HighResTimer;
+use crate::hook::ParkHook;
+use crate::pad::CachePadded;
+use crate::pool::Pool;
+#[cfg(target_vendor = "apple")]
+use crate::qos::QosOverride;
+#[cfg(feature = "registry")]
+use crate::registry::Registered;
+use crate::spin;
+use crate::state;
+#[cfg(feature = "diagnostics")]
+use crate::stats::{ParkStats, Stats};
+use crate::timers::{self, ScheduledUnpark};
+#[cfg(parking_tsan)]
+use crate::tsan;
+#[cfg(feature = "metrics")]
+use crate::metrics::{self, Metrics};
+#[cfg(feature = "mock-clock")]
+use crate::mock::MockClock;
+use crate::observer::{self, Observer};
+use crate::watchdog::{StallClock, Watchdog};
+use crate::ParkerBuilder;
+
+pub fn pair() -> (Parker, Unparker) {
+    let p = Parker::new();
+    let u = p.unparker();
+    (p, u)
+}
+
+/// Blocks until any of `parkers` is notified, consumes that notification and returns the index
+/// of the parker it belonged to
+///
+/// If several parkers are notified, only the one with the lowest index is consumed.
+///
+/// # Panics
+///
+/// Panics if `parkers` is empty
+pub fn park_any(parkers: &[&Parker]) -> usize {
+    select(parkers, None).expect("untimed park_any returned without a notification")
+}
+
+/// Blocks until any of `parkers` is notified, or times out after `duration`
+///
+/// return the index of the notified parker, or `None` on timeout
+///
+/// # Panics
+///
+/// Panics if `parkers` is empty
+pub fn park_any_timeout(parkers: &[&Parker], duration: Duration) -> Option<usize> {
+    select(parkers, deadline_after(duration))
+}
+
+/// Blocks until any of `parkers` is notified, or times out at `instant`
+///
+/// return the index of the notified parker, or `None` on timeout
+///
+/// # Panics
+///
+/// Panics if `parkers` is empty
+pub fn park_any_deadline(parkers: &[&Parker], instant: Instant) -> Option<usize> {
+    select(parkers, Some(instant))
+}
+
+thread_local! {
+    /// Parked on by `park_any` while it watches a set of parkers
+    static SELECTOR: Parker = Parker::new();
+}
+
+fn select(parkers: &[&Parker], deadline: Option<Instant>) -> Option<usize> {
+    assert!(!parkers.is_empty(), "park_any requires at least one parker");
+
+    let poll = || parkers.iter().position(|p| p.inner.poll(deadline));
+    if let Some(i) = poll() {
+        return Some(i);
+    }
+
+    SELECTOR.with(|selector| {
+        // Register the selector with every parker before checking them again, so that any
+        // notification arriving after the check also wakes the selector
+        for p in parkers {
+            p.inner.watch(selector.unparker());
+        }
+
+        let fired = loop {
+            if let Some(i) = poll() {
+                break Some(i);
+            }
+            match deadline {
+                None => selector.park(),
+                Some(deadline) => {
+                    if !selector.park_deadline(deadline) {
+                        break poll();
+                    }
+                }
+            }
+        };
+
+        for p in parkers {
+            p.inner.unwatch();
+        }
+        fired
+    })
+}
+
+/// Waits for a notification
+pub struct Parker {
+    pub(crate) inner: Arc<Inner>,
+    _marker: PhantomData<Cell<()>>
+}
+
+impl Parker {
+
+    pub fn new() -> Parker {
+        ParkerBuilder::new().build()
+    }
+
+    pub(crate) fn from_inner(inner: Arc<Inner>) -> Parker {
+        Parker {
+            inner,
+            _marker: PhantomData
+        }
+    }
+
+    /// Blocks until notified and then goes back into unnotified state
+    pub fn park(&self) {
+        self.inner.park(None);
+    }
+
+    /// Blocks until notified and then goes back into unnotified state, or times out after `duration`
+    ///
+    /// return `true` if notified before the timeout
+    ///
+    /// A `duration` too long for a deadline to be computed, such as `Duration::MAX`, never times
+    /// out, while a zero `duration` never blocks.
+    pub fn park_timeout(&self, duration: Duration) -> bool {
+        self.inner.park(self.inner.deadline_after(duration)).notified
+    }
+
+    /// Blocks until notified and then goes back into unnotified state, or times out at `instant`
+    ///
+    /// return `true` if notified before the deadline
+    pub fn park_deadline(&self, instant: Instant) -> bool {
+        self.inner.park(Some(instant)).notified
+    }
+
+    /// Blocks until notified and then goes back into unnotified state, or until `deadline`
+    ///
+    /// Takes a `Deadline`, a `Duration` or an `Instant`, giving generic code one call in place of
+    /// `park`, `park_timeout` and `park_deadline`.
+    pub fn park_until(&self, deadline: impl Into<Deadline>) -> ParkResult {
+        let deadline = deadline.into();
+        let deadline = match deadline.duration() {
+            Some(duration) => self.inner.deadline_after(duration),
+            None => deadline.instant()
+        };
+        if self.inner.park(deadline).notified {
+            ParkResult::Notified
+        } else {
+            ParkResult::TimedOut
+        }
+    }
+
+    /// Like `park_timeout`, but reports how the park ended and how many spurious wakeups of the
+    /// underlying condition variable were absorbed along the way
+    #[cfg(feature = "diagnostics")]
+    pub fn park_timeout_outcome(&self, duration: Duration) -> ParkOutcome {
+        self.inner.park(self.inner.deadline_after(duration)).into()
+    }
+
+    /// Like `park_deadline`, but reports how the park ended and how many spurious wakeups of the
+    /// underlying condition variable were absorbed along the way
+    #[cfg(feature = "diagnostics")]
+    pub fn park_deadline_outcome(&self, instant: Instant) -> ParkOutcome {
+        self.inner.park(Some(instant)).into()
+    }
+
+    /// Notifies the parker
+    ///
+    /// return `true` if this call is the first to notify the parker, or `false`
+    /// if the parker was already notified
+    pub fn unpark(&self) -> bool {
+        self.inner.unpark()
+    }
+
+    /// Like `park`, but polls for a notification up to `spins` times before blocking, in place
+    /// of the parker's own spin count from `ParkerBuilder::spin`
+    pub fn park_spin(&self, spins: u32) {
+        self.inner.park_spin(None, spins);
+    }
+
+    /// Like `park_timeout`, but polls for a notification up to `spins` times before blocking, in
+    /// place of the parker's own spin count from `ParkerBuilder::spin`
+    ///
+    /// return `true` if notified before the timeout
+    pub fn park_timeout_spin(&self, duration: Duration, spins: u32) -> bool {
+        self.inner.park_spin(self.inner.deadline_after(duration), spins).notified
+    }
+
+    /// Takes the wake reasons unparkers set with `Unparker::unpark_flags`, parking until some are
+    /// set if there are none
+    ///
+    /// Every bit set since the last call is returned, and cleared. A plain `unpark`, which sets
+    /// none, wakes the park as well, and then this returns 0.
+    pub fn park_flags(&self) -> usize {
+        self.inner.park_flags(None)
+    }
+
+    /// Like `park_flags`, but parks at most for `duration`
+    ///
+    /// return 0 if it timed out without any wake reasons set
+    pub fn park_flags_timeout(&self, duration: Duration) -> usize {
+        self.inner.park_flags(self.inner.deadline_after(duration))
+    }
+
+    /// Like `park`, but returns the parker's generation: the number of notifications its parks
+    /// have consumed so far, wrapping around on overflow
+    ///
+    /// Paired with `Unparker::unpark_if_gen`, this lets a poll loop skip wakeups it doesn't need.
+    /// Store the returned generation where unparkers can see it, look for work, and park again
+    /// if there is none. An unparker publishes its work, reads the stored generation and passes
+    /// it to `unpark_if_gen`.
+    pub fn park_gen(&self) -> usize {
+        self.inner.park(None);
+        self.generation()
+    }
+
+    /// Return the number of notifications this parker's parks have consumed so far, see
+    /// `park_gen`
+    pub fn generation(&self) -> usize {
+        self.inner.generation.load(SeqCst)
+    }
+
+    /// Return a handle for unparking
+    pub fn unparker(&self) -> Unparker {
+        Unparker {
+            handle: Handle::Parker(self.inner.clone())
+        }
+    }
+
+    /// Converts the parker into a handle for unparking, for code that only hands out wake handles
+    /// and never parks itself
+    pub fn into_unparker(self) -> Unparker {
+        // Moves `inner` out instead of cloning it, skipping `Drop` and its pool recycling
+        let parker = ManuallyDrop::new(self);
+        // SAFETY: `parker` is never used or dropped again, so `inner` is moved out only once
+        let inner = unsafe { std::ptr::read(&parker.inner) };
+        inner.parker_alive.store(false, SeqCst);
+        Unparker {
+            handle: Handle::Parker(inner)
+        }
+    }
+
+    /// Return an identifier for this parker, unique within the process
+    pub fn id(&self) -> usize {
+        self.inner.id
+    }
+
+    /// Return the number of live `Unparker` handles for this parker
+    pub fn handle_count(&self) -> usize {
+        Arc::strong_count(&self.inner) - 1
+    }
+
+    /// Return a snapshot of this parker's activity counters
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> Metrics {
+        self.inner.metrics.snapshot()
+    }
+
+    /// Return a snapshot of this parker's histograms of time spent parked and of latency from
+    /// unpark to wakeup
+    ///
+    /// Every unpark takes a timestamp for the latency, which is why these are only kept with the
+    /// `diagnostics` feature.
+    #[cfg(feature = "diagnostics")]
+    pub fn stats(&self) -> ParkStats {
+        self.inner.stats.snapshot()
+    }
+
+    /// Return a snapshot of the parker's state, for finding out what a hung thread waits for
+    ///
+    /// The `Debug` output of the parker shows the same.
+    pub fn debug_state(&self) -> DebugState {
+        self.inner.debug_state()
+    }
+}
+
+impl Drop for Parker {
+    fn drop(&mut self) {
+        self.inner.parker_alive.store(false, SeqCst);
+        // With no unparker left, nothing can tell a recycled parker from a new one
+        if let Some(pool) = self.inner.pool.as_ref().and_then(Weak::upgrade) {
+            if Arc::strong_count(&self.inner) == 1 {
+                pool.recycle(self.inner.clone());
+            }
+        }
+    }
+}
+
+impl Default for Parker {
+    fn default() -> Self {
+        Parker::new()
+    }
+}
+
+// The state machine is consistent whenever a panic can unwind out of a park: with `state` back
+// to `EMPTY` or `NOTIFIED` and no locks held across user callbacks, so nothing is left poisoned
+impl UnwindSafe for Parker {}
+impl RefUnwindSafe for Parker {}
+
+impl std::fmt::Debug for Parker {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.debug_state().fmt_as("Parker", f)
+    }
+}
+
+/// Notifies a parker
+pub struct Unparker {
+    pub(crate) handle: Handle
+}
+
+/// What an `Unparker` wakes up
+#[derive(Clone)]
+pub(crate) enum Handle {
+    Parker(Arc<Inner>),
+    /// Bridged from elsewhere through `Unparker::from_waker`, `Unparker::from_thread` and the like
+    Foreign(Arc<Foreign>)
+}
+
+impl Unparker {
+    /// Notifies the parker
+    ///
+    /// return `true` if this call is the first to notify the parker, or `false` if the parker
+    /// was already notified. Unparkers created with `from_waker` or `from_thread` can't tell and
+    /// always return `true`.
+    pub fn unpark(&self) -> bool {
+        match &self.handle {
+            Handle::Parker(inner) => inner.unpark(),
+            Handle::Foreign(foreign) => foreign.wake()
+        }
+    }
+
+    /// Sets `flags` among the parker's wake reasons and notifies it, see `Parker::park_flags`
+    ///
+    /// The bits are set before notifying, so the park they wake returns them. Unparkers created
+    /// with `from_waker` or `from_thread` have nowhere to keep them and only notify. One created
+    /// with `with_mio_waker` sets them on its parker and then wakes the poll.
+    ///
+    /// return `true` if this call is the first to notify the parker, like `unpark`
+    pub fn unpark_flags(&self, flags: usize) -> bool {
+        match &self.handle {
+            Handle::Parker(inner) => {
+                inner.flags.fetch_or(flags, SeqCst);
+                inner.unpark()
+            }
+            Handle::Foreign(foreign) => foreign.unpark_flags(flags)
+        }
+    }
+
+    /// Wakes the parked thread if it's blocked in a park right now, and otherwise does nothing,
+    /// leaving no notification for the next park
+    ///
+    /// For loops that check their own condition before every park, where a stored notification
+    /// only causes a wakeup that finds nothing to do. The other side of that is that a thread
+    /// between its check and blocking is not woken: it blocks and misses this call, so the
+    /// condition may stay unseen until something else wakes it. Pair this with timed parks
+    /// that bound how long that can take, or use `unpark` wherever a missed wakeup would hang.
+    /// Waits in `park_any` and spin polls don't count as blocked. Unparkers created with
+    /// `from_waker` or `from_thread` can't tell and always notify. One created with
+    /// `with_mio_waker` passes the call on to its parker, and wakes the poll only if that woke
+    /// a blocked thread.
+    ///
+    /// return `true` if a blocked thread was woken
+    pub fn unpark_now(&self) -> bool {
+        match &self.handle {
+            Handle::Parker(inner) => inner.unpark_now(),
+            Handle::Foreign(foreign) => foreign.unpark_now()
+        }
+    }
+
+    /// Notifies the parker unless it has consumed a notification since returning `generation`
+    /// from `Parker::park_gen`
+    ///
+    /// A skipped notification is never lost, as the parker has since woken and looks for work
+    /// again after the unparker published its own, provided both follow the protocol described
+    /// on `park_gen`. Unparkers created with `from_waker` or `from_thread` always notify. One
+    /// created with `with_mio_waker` checks the generation of its parker, and wakes the poll
+    /// only if it notifies.
+    ///
+    /// return `true` if this call is the first to notify the parker
+    pub fn unpark_if_gen(&self, generation: usize) -> bool {
+        match self.parker() {
+            Some(inner) if inner.generation.load(SeqCst) != generation => false,
+            _ => self.unpark()
+        }
+    }
+
+    /// Notifies the parker at `instant`, from a background thread shared by every scheduled
+    /// unpark in the process
+    ///
+    /// The returned handle cancels the unpark, which otherwise goes ahead even if it's dropped.
+    pub fn unpark_at(&self, instant: Instant) -> ScheduledUnpark {
+        ScheduledUnpark::new(timers::global().insert(instant, self.clone()))
+    }
+
+    /// Notifies the parker once `duration` has passed, see `unpark_at`
+    ///
+    /// A `duration` too long for a deadline to be computed, such as `Duration::MAX`, never comes.
+    pub fn unpark_after(&self, duration: Duration) -> ScheduledUnpark {
+        ScheduledUnpark::new(timers::global().insert_after(duration, self.clone()))
+    }
+
+    /// Return the identifier of the parker this handle notifies
+    ///
+    /// Unparkers created with `from_waker` or `from_thread` get an identifier of their own, shared
+    /// by their clones.
+    pub fn id(&self) -> usize {
+        match &self.handle {
+            Handle::Parker(inner) => inner.id,
+            Handle::Foreign(foreign) => foreign.id
+        }
+    }
+
+    /// Return the number of live `Unparker` handles for the same parker, including this one
+    ///
+    /// The handle owned by the `Parker` itself is not counted. Like any count of shared handles,
+    /// the value may already be stale by the time it is returned if other threads are cloning
+    /// or dropping handles concurrently.
+    pub fn handle_count(&self) -> usize {
+        match &self.handle {
+            Handle::Parker(inner) => {
+                let parker = inner.parker_alive.load(SeqCst) as usize;
+                Arc::strong_count(inner) - parker
+            }
+            Handle::Foreign(foreign) => Arc::strong_count(foreign)
+        }
+    }
+
+    /// Return a snapshot of the state of the parker this handle notifies, see
+    /// `Parker::debug_state`
+    ///
+    /// return `None` for unparkers created with `from_waker` or `from_thread`, which have no
+    /// parker to look at. One created with `with_mio_waker` returns the state of its parker.
+    pub fn debug_state(&self) -> Option<DebugState> {
+        self.parker().map(Inner::debug_state)
+    }
+
+    /// Return the parker this handle notifies, looking through `with_mio_waker`
+    pub(crate) fn parker(&self) -> Option<&Arc<Inner>> {
+        match &self.handle {
+            Handle::Parker(inner) => Some(inner),
+            Handle::Foreign(foreign) => foreign.parker()
+        }
+    }
+
+    /// Consumes the unparker, returning a pointer that `from_raw` turns back into it
+    ///
+    /// The pointer is opaque, for keeping the handle in an `AtomicPtr`, an intrusive node or the
+    /// `void *` of a C callback. It owns the handle, which leaks unless it comes back through
+    /// `from_raw`. It is never null.
+    pub fn into_raw(self) -> *const () {
+        match self.handle {
+            Handle::Parker(inner) => Arc::into_raw(inner).cast(),
+            Handle::Foreign(foreign) => Arc::into_raw(foreign).cast::<u8>().wrapping_add(FOREIGN_TAG).cast()
+        }
+    }
+
+    /// Turns a pointer from `into_raw` back into the unparker
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from `into_raw`, and each pointer it returned may be turned back once.
+    pub unsafe fn from_raw(ptr: *const ()) -> Unparker {
+        let handle = if ptr as usize & FOREIGN_TAG == 0 {
+            Handle::Parker(Arc::from_raw(ptr.cast()))
+        } else {
+            Handle::Foreign(Arc::from_raw(ptr.cast::<u8>().wrapping_sub(FOREIGN_TAG).cast()))
+        };
+        Unparker { handle }
+    }
+
+    /// Return a new unparker for the same parker as the pointer from `into_raw`, which stays
+    /// valid
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from `into_raw` and not have been turned back by `from_raw` yet.
+    pub unsafe fn clone_raw(ptr: *const ()) -> Unparker {
+        let unparker = ManuallyDrop::new(Unparker::from_raw(ptr));
+        Unparker::clone(&unparker)
+    }
+}
+
+/// Set in pointers from `Unparker::into_raw` to a `Foreign`, which, holding a `usize`, is aligned
+/// to at least two bytes, as is `Inner`
+const FOREIGN_TAG: usize = 1;
+
+const _: () = assert!(std::mem::align_of::<Foreign>() > FOREIGN_TAG && std::mem::align_of::<Inner>() > FOREIGN_TAG);
+
+impl std::fmt::Debug for Unparker {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.handle {
+            Handle::Parker(inner) => inner.debug_state().fmt_as("Unparker", f),
+            Handle::Foreign(foreign) => f.debug_struct("Unparker").field("id", &foreign.id).finish_non_exhaustive()
+        }
+    }
+}
+
+impl Clone for Unparker {
+    fn clone(&self) -> Self {
+        Unparker {
+            handle: self.handle.clone()
+        }
+    }
+}
+
+// Unparking is a single swap plus a wakeup, with nothing to leave half done
+impl UnwindSafe for Unparker {}
+impl RefUnwindSafe for Unparker {}
+
+/// How a timed park ended
+#[cfg(feature = "diagnostics")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParkOutcome {
+    /// The parker was notified before the timeout
+    Notified {
+        /// Number of times the condition variable woke up without a notification
+        spurious_wakeups: usize
+    },
+    /// The timeout elapsed without a notification
+    TimedOut {
+        /// Number of times the condition variable woke up without a notification
+        spurious_wakeups: usize
+    }
+}
+
+#[cfg(feature = "diagnostics")]
+impl ParkOutcome {
+    /// return `true` if the park ended because of a notification
+    pub fn is_notified(&self) -> bool {
+        matches!(self, ParkOutcome::Notified { .. })
+    }
+
+    /// return the number of spurious wakeups absorbed during the park
+    pub fn spurious_wakeups(&self) -> usize {
+        match *self {
+            ParkOutcome::Notified { spurious_wakeups } => spurious_wakeups,
+            ParkOutcome::TimedOut { spurious_wakeups } => spurious_wakeups
+        }
+    }
+}
+
+#[cfg(feature = "diagnostics")]
+impl From<Wakeup> for ParkOutcome {
+    fn from(wakeup: Wakeup) -> Self {
+        let spurious_wakeups = wakeup.spurious;
+        if wakeup.notified {
+            ParkOutcome::Notified { spurious_wakeups }
+        } else {
+            ParkOutcome::TimedOut { spurious_wakeups }
+        }
+    }
+}
+
+/// Result of `Inner::park`: whether a notification was consumed, and how many times the
+/// condition variable woke up without one
+pub(crate) struct Wakeup {
+    notified: bool,
+    #[cfg_attr(not(any(feature = "diagnostics", feature = "tracing")), allow(dead_code))]
+    spurious: usize
+}
+
+impl Wakeup {
+    pub(crate) fn notified(spurious: usize) -> Wakeup {
+        Wakeup { notified: true, spurious }
+    }
+
+    pub(crate) fn timed_out(spurious: usize) -> Wakeup {
+        Wakeup { notified: false, spurious }
+    }
+}
+
+/// The bookkeeping of a park in progress, from `Inner::begin_record` to `ParkRecord::end`: the
+/// debug-checks claim on the parker, the generation, the observer, metrics, diagnostics and
+/// tracing
+pub(crate) struct ParkRecord<'a> {
+    inner: &'a Inner,
+    #[cfg(feature = "debug-checks")]
+    _parking: Parking<'a>,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    #[cfg(any(feature = "diagnostics", feature = "tracing"))]
+    start: Instant,
+    observed: Option<(&'static dyn Observer, Instant)>
+}
+
+impl ParkRecord<'_> {
+    /// Finishes the bookkeeping of a park that ended with `wakeup`
+    fn end(self, wakeup: &Wakeup) {
+        let inner = self.inner;
+        if wakeup.notified {
+            inner.count_consumed();
+        }
+
+        if let Some((observer, start)) = self.observed {
+            observer.on_wakeup(inner.id, wakeup.notified, start.elapsed());
+        }
+
+        #[cfg(feature = "metrics")]
+        inner.metrics.record_park(wakeup.notified);
+        #[cfg(feature = "diagnostics")]
+        inner.stats.record_park(self.start.elapsed());
+        #[cfg(feature = "tracing")]
+        self.span.in_scope(|| tracing::trace!(
+            parker = inner.id,
+            reason = if wakeup.notified { "notified" } else { "timed out" },
+            spurious_wakeups = wakeup.spurious,
+            elapsed = ?self.start.elapsed(),
+            "park end"
+        ));
+    }
+}
+
+/// Return the deadline `duration` from now, or `None` if it's too far out for `Instant`, which
+/// is centuries on every platform, making the timeout as good as none
+fn deadline_after(duration: Duration) -> Option<Instant> {
+    Instant::now().checked_add(duration)
+}
+
+/// Source of parker identifiers
+pub(crate) static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) use crate::state::{EMPTY, NOTIFIED, PARKED};
+
+/// Laid out so that what every handoff writes, `state` and the backend's `waiter`, shares one
+/// cache line, what only the parking thread writes gets the next, and the rest, mostly set once
+/// and read, comes after. Unparkers on other cores then only ever pull in the first line. The
+/// `metrics` and `stats` that unparkers record sit on lines of their own, apart from those the
+/// parking thread records on. `flags`, written by unparkers and taken by the parking thread like
+/// `state`, shares its line.
+#[repr(C)]
+pub(crate) struct Inner {
+    /// Futex-sized, so the futex backend can wait on it directly
+    state: AtomicU32,
+    waiter: backend::Waiter,
+    /// Wake reasons set by `Unparker::unpark_flags` and not yet taken by `Parker::park_flags`
+    flags: AtomicUsize,
+    /// Number of notifications consumed, see `Parker::park_gen`
+    generation: CachePadded<AtomicUsize>,
+    id: usize,
+    /// Cleared when the `Parker` is dropped, so its handle is no longer discounted in `handle_count`
+    parker_alive: AtomicBool,
+    /// Set while `park_any` is waiting on this parker through `watcher`
+    watched: AtomicBool,
+    watcher: Mutex<Option<Unparker>>,
+    clock: Clock,
+    /// Polls before blocking, see `ParkerBuilder::spin`
+    spins: u32,
+    #[cfg(target_vendor = "apple")]
+    qos: Option<QosOverride>,
+    #[cfg(windows)]
+    boost: Option<PriorityBoost>,
+    /// Times timed parks instead of the backend, see `ParkerBuilder::high_resolution`
+    #[cfg(windows)]
+    hires: Option<HighResTimer>,
+    watchdog: Option<Watchdog>,
+    hook: Option<ParkHook>,
+    /// Pool the `Parker` came from, which takes this back when it's dropped
+    pub(crate) pool: Option<Weak<Pool>>,
+    #[cfg(feature = "metrics")]
+    metrics: metrics::Counters,
+    #[cfg(feature = "diagnostics")]
+    stats: Stats,
+    #[cfg(feature = "debug-checks")]
+    owner: Owner,
+    /// Clock to measure timeouts on in place of `clock`, see `ParkerBuilder::mock_clock`
+    #[cfg(feature = "mock-clock")]
+    mock: Option<MockClock>
+}
+
+/// Tidies up after `Inner::wait` blocks, also when a watchdog callback unwinds out of it
+struct Blocked<'a>(&'a Inner);
+
+impl Drop for Blocked<'_> {
+    fn drop(&mut self) {
+        #[cfg(target_vendor = "apple")]
+        if let Some(qos) = &self.0.qos {
+            qos.end_park();
+        }
+        #[cfg(windows)]
+        if let Some(boost) = &self.0.boost {
+            boost.end_park();
+        }
+        // Backends return with `state` back to `EMPTY`, so only an unwinding park leaves it
+        // `PARKED`. A notification that raced with the panic stays for the next park.
+        if thread::panicking() {
+            let _ = self.0.state.compare_exchange(PARKED, EMPTY, SeqCst, SeqCst);
+        }
+    }
+}
+
+impl Inner {
+
+    pub(crate) fn new(
+        clock: Clock,
+        spins: u32,
+        #[cfg(target_vendor = "apple")] qos: Option<QosOverride>,
+        #[cfg(windows)] boost: Option<PriorityBoost>,
+        #[cfg(windows)] hires: Option<HighResTimer>,
+        watchdog: Option<Watchdog>,
+        hook: Option<ParkHook>,
+        #[cfg(feature = "mock-clock")] mock: Option<MockClock>
+    ) -> Inner {
+        Inner {
+            state: AtomicU32::new(EMPTY),
+            waiter: backend::Waiter::new(),
+            flags: AtomicUsize::new(0),
+            generation: CachePadded::new(AtomicUsize::new(0)),
+            id: NEXT_ID.fetch_add(1, Relaxed),
+            parker_alive: AtomicBool::new(true),
+            watched: AtomicBool::new(false),
+            watcher: Mutex::new(None),
+            clock,
+            spins,
+            #[cfg(target_vendor = "apple")]
+            qos,
+            #[cfg(windows)]
+            boost,
+            #[cfg(windows)]
+            hires,
+            watchdog,
+            hook,
+            pool: None,
+            #[cfg(feature = "metrics")]
+            metrics: metrics::Counters::new(),
+            #[cfg(feature = "diagnostics")]
+            stats: Stats::new(),
+            #[cfg(feature = "debug-checks")]
+            owner: Owner::new(),
+            #[cfg(feature = "mock-clock")]
+            mock
+        }
+    }
+
+    /// Makes a recycled `Inner` as good as new, keeping its configuration
+    pub(crate) fn reset(&mut self) {
+        self.id = NEXT_ID.fetch_add(1, Relaxed);
+        // A notification left by the previous owner's unparkers must not reach the next owner
+        *self.state.get_mut() = EMPTY;
+        *self.flags.get_mut() = 0;
+        *self.generation.get_mut() = 0;
+        *self.parker_alive.get_mut() = true;
+        *self.watched.get_mut() = false;
+        *self.watcher.get_mut().unwrap_or_else(PoisonError::into_inner) = None;
+        #[cfg(feature = "metrics")]
+        {
+            self.metrics = metrics::Counters::new();
+        }
+        #[cfg(feature = "diagnostics")]
+        {
+            self.stats = Stats::new();
+        }
+    }
+
+    /// Advances the generation after a park consumed a notification, see `Parker::park_gen`
+    fn count_consumed(&self) {
+        #[cfg(parking_tsan)]
+        tsan::acquire(&self.state);
+        // Only the parking thread writes `generation`
+        self.generation.store(self.generation.load(Relaxed).wrapping_add(1), SeqCst);
+    }
+
+    /// Return the current time on the clock timeouts are measured on
+    fn now(&self) -> Instant {
+        #[cfg(feature = "mock-clock")]
+        if let Some(mock) = &self.mock {
+            return mock.now();
+        }
+        self.clock.now()
+    }
+
+    /// Return the deadline `duration` from now on the clock timeouts are measured on, see
+    /// `deadline_after`
+    fn deadline_after(&self, duration: Duration) -> Option<Instant> {
+        #[cfg(feature = "mock-clock")]
+        if let Some(mock) = &self.mock {
+            return mock.now().checked_add(duration);
+        }
+        // Even with `Clock::Coarse`, which lags behind and would cut the timeout short
+        deadline_after(duration)
+    }
+
+    /// Consumes a pending notification without blocking
+    fn try_consume(&self) -> bool {
+        state::try_consume(&self.state)
+    }
+
+    /// Takes the wake reasons, parking until `deadline` first if there are none, see
+    /// `Parker::park_flags`
+    fn park_flags(&self, deadline: Option<Instant>) -> usize {
+        let flags = self.flags.swap(0, SeqCst);
+        if flags != 0 {
+            // The notification that came with them would only end the next park for nothing.
+            // One from an `unpark_flags` after the swap goes too, but leaves its bits for the
+            // next call to return at once.
+            self.poll(deadline);
+            return flags;
+        }
+        self.park(deadline);
+        self.flags.swap(0, SeqCst)
+    }
+
+    fn park(&self, deadline: Option<Instant>) -> Wakeup {
+        self.park_spin(deadline, self.spins)
+    }
+
+    /// Parks after polling for a notification up to `spins` times, see `ParkerBuilder::spin`
+    fn park_spin(&self, deadline: Option<Instant>, spins: u32) -> Wakeup {
+        self.park_with(deadline, || self.wait(deadline, spins))
+    }
+
+    /// Consumes a pending notification without blocking, counted as a park that returned at
+    /// once, for `park_any` polling the parkers it watches and `park_flags` finding flags set
+    ///
+    /// return `true` if there was one
+    fn poll(&self, deadline: Option<Instant>) -> bool {
+        // Only parks consume, and they happen on the thread owning the parker, so a notification
+        // seen here is still there below. Nothing is recorded for a poll that finds none.
+        if self.state.load(SeqCst) != NOTIFIED {
+            return false;
+        }
+        self.park_with(deadline, || {
+            if self.try_consume() { Wakeup::notified(0) } else { Wakeup::timed_out(0) }
+        }).notified
+    }
+
+    /// Runs `wait`, which parks until `deadline`, with the bookkeeping every park gets, see
+    /// `ParkRecord`
+    fn park_with<F>(&self, deadline: Option<Instant>, wait: F) -> Wakeup
+        where F: FnOnce() -> Wakeup
+    {
+        let record = self.begin_record(deadline);
+        let wakeup = {
+            #[cfg(feature = "tracing")]
+            let _span = record.span.enter();
+            wait()
+        };
+        record.end(&wakeup);
+        wakeup
+    }
+
+    /// Starts the bookkeeping of a park until `deadline`
+    fn begin_record(&self, deadline: Option<Instant>) -> ParkRecord<'_> {
+        #[cfg(feature = "debug-checks")]
+        let parking = self.owner.enter(self.id);
+        #[cfg(feature = "tracing")]
+        let span = tracing::trace_span!("park", parker = self.id, deadline = ?deadline);
+        #[cfg(any(feature = "diagnostics", feature = "tracing"))]
+        let start = Instant::now();
+        #[cfg(feature = "tracing")]
+        span.in_scope(|| tracing::trace!(parker = self.id, "park begin"));
+        let observed = observer::get().map(|observer| {
+            observer.on_park(self.id, deadline);
+            (observer, Instant::now())
+        });
+        ParkRecord {
+            inner: self,
+            #[cfg(feature = "debug-checks")]
+            _parking: parking,
+            #[cfg(feature = "tracing")]
+            span,
+            #[cfg(any(feature = "diagnostics", feature = "tracing"))]
+            start,
+            observed
+        }
+    }
+
+    fn wait(&self, deadline: Option<Instant>, spins: u32) -> Wakeup {
+        if self.try_consume() {
+            return Wakeup::notified(0);
+        }
+
+        // If the deadline has passed, then there is no need to actually block
+        if let Some(deadline) = deadline {
+            if deadline <= self.now() {
+                return Wakeup::timed_out(0);
+            }
+        }
+
+        for _ in 0..spins {
+            spin::wait_while(&self.state, EMPTY);
+            if self.try_consume() {
+                return Wakeup::notified(0);
+            }
+        }
+
+        if let Some(hook) = &self.hook {
+            (hook.0)(deadline.map(|deadline| deadline.saturating_duration_since(self.now())));
+            if self.try_consume() {
+                return Wakeup::notified(0);
+            }
+            if let Some(deadline) = deadline {
+                if deadline <= self.now() {
+                    return Wakeup::timed_out(0);
+                }
+            }
+        }
+
+        #[cfg(target_vendor = "apple")]
+        if let Some(qos) = &self.qos {
+            qos.begin_park();
+        }
+        #[cfg(windows)]
+        if let Some(boost) = &self.boost {
+            boost.begin_park();
+        }
+        let _blocked = Blocked(self);
+        #[cfg(feature = "registry")]
+        let _registered = Registered::new(self.id, deadline);
+        let mut stall = self.watchdog.as_ref().map(|watchdog| StallClock::start(watchdog, self.id));
+        #[cfg(feature = "diagnostics")]
+        let blocked_since = Instant::now();
+        let wakeup = match (deadline, self.clock) {
+            #[cfg(feature = "mock-clock")]
+            (Some(deadline), _) if self.mock.is_some() => self.wait_mock(deadline, &mut stall),
+            #[cfg(windows)]
+            (Some(deadline), _) if self.hires.is_some() => self.wait_hires(deadline, &mut stall),
+            (Some(deadline), Clock::Boottime) => match clock::boottime() {
+                Some(now) => match now.checked_add(deadline.saturating_duration_since(Instant::now())) {
+                    Some(deadline) => self.wait_boottime(deadline, &mut stall),
+                    None => self.waiter.park(&self.state, None, self.clock, &mut stall)
+                },
+                None => self.waiter.park(&self.state, Some(deadline), self.clock, &mut stall)
+            },
+            _ => self.waiter.park(&self.state, deadline, self.clock, &mut stall)
+        };
+        #[cfg(feature = "diagnostics")]
+        if wakeup.notified {
+            self.stats.record_wakeup(blocked_since);
+        }
+        wakeup
+    }
+
+    /// Moves to `PARKED` for a wait that happens outside the backend, returning the value that
+    /// the futex word must keep for the wait to go on along with the park's bookkeeping, or
+    /// `None` after consuming a pending notification instead
+    #[cfg(all(feature = "io-uring", parking_futex = "linux", target_os = "linux"))]
+    pub(crate) fn begin_external_wait(&self) -> Option<(u32, ParkRecord<'_>)> {
+        let record = self.begin_record(None);
+        if state::begin_park(&self.state) {
+            // An unpark from here on writes `NOTIFIED` before waking, so the wait completes at once
+            Some((PARKED, record))
+        } else {
+            record.end(&Wakeup::notified(0));
+            None
+        }
+    }
+
+    /// Returns to `EMPTY` after `begin_external_wait`, finishing the park's `record`
+    ///
+    /// return `true` if notified in the meantime
+    #[cfg(all(feature = "io-uring", parking_futex = "linux", target_os = "linux"))]
+    pub(crate) fn end_external_wait(&self, record: ParkRecord<'_>) -> bool {
+        let notified = state::end_park(&self.state);
+        #[cfg(feature = "diagnostics")]
+        if notified {
+            self.stats.record_wakeup(record.start);
+        }
+        record.end(&if notified { Wakeup::notified(0) } else { Wakeup::timed_out(0) });
+        notified
+    }
+
+    #[cfg(all(feature = "io-uring", parking_futex = "linux", target_os = "linux"))]
+    pub(crate) fn futex(&self) -> &AtomicU32 {
+        &self.state
+    }
+
+    /// Parks on the mock clock until `deadline`, see `ParkerBuilder::mock_clock`
+    #[cfg(feature = "mock-clock")]
+    fn wait_mock(&self, deadline: Instant, stall: &mut Option<StallClock<'_>>) -> Wakeup {
+        match self.mock.as_ref().map(|mock| mock.park(&self.state, deadline, stall)) {
+            Some(true) => Wakeup::notified(0),
+            _ => Wakeup::timed_out(0)
+        }
+    }
+
+    /// Parks until `deadline` on the high-resolution timer, see `ParkerBuilder::high_resolution`
+    #[cfg(windows)]
+    fn wait_hires(&self, deadline: Instant, stall: &mut Option<StallClock<'_>>) -> Wakeup {
+        match self.hires.as_ref().map(|hires| hires.park(&self.state, deadline, stall)) {
+            Some(true) => Wakeup::notified(0),
+            _ => Wakeup::timed_out(0)
+        }
+    }
+
+    /// Parks in slices until `deadline` on the boot time clock, so that time spent suspended
+    /// counts towards the timeout
+    fn wait_boottime(&self, deadline: Duration, stall: &mut Option<StallClock<'_>>) -> Wakeup {
+        let mut spurious = 0;
+        loop {
+            let remaining = clock::boottime().map_or(Duration::from_millis(0), |now| deadline.saturating_sub(now));
+            if remaining == Duration::from_millis(0) {
+                return Wakeup::timed_out(spurious);
+            }
+            let wakeup = self.waiter.park(&self.state, Some(Instant::now() + remaining.min(BOOTTIME_SLICE)), Clock::Monotonic, stall);
+            spurious += wakeup.spurious;
+            if wakeup.notified {
+                return Wakeup::notified(spurious);
+            }
+        }
+    }
+
+    pub fn unpark(&self) -> bool {
+        #[cfg(parking_tsan)]
+        tsan::release(&self.state);
+        #[cfg(feature = "diagnostics")]
+        self.stats.record_unpark();
+        match state::notify(&self.state) {
+            EMPTY => {                 // no one was waiting, except maybe `park_any`
+                self.wake_watcher();
+                self.record_unpark(true, false);
+                return true;
+            }
+            NOTIFIED => {              // already unparked
+                self.record_unpark(false, false);
+                return false;
+            }
+            PARKED => {},              // gotta go wake someone up
+            _ => panic!("inconsistent state in unpark")
+        }
+        self.wake();
+        true
+    }
+
+    fn debug_state(self: &Arc<Self>) -> DebugState {
+        let state = self.state.load(SeqCst);
+        let parker_alive = self.parker_alive.load(SeqCst);
+        DebugState {
+            parker_id: self.id,
+            state,
+            blocked: state == PARKED,
+            notified: state == NOTIFIED,
+            handle_count: Arc::strong_count(self) - parker_alive as usize,
+            parker_alive,
+            generation: self.generation.load(SeqCst),
+            flags: self.flags.load(SeqCst),
+            #[cfg(feature = "diagnostics")]
+            last_unpark: self.stats.last_unpark()
+        }
+    }
+
+    /// Notifies the parked thread only if it's blocked, see `Unparker::unpark_now`
+    pub(crate) fn unpark_now(&self) -> bool {
+        #[cfg(feature = "debug-checks")]
+        self.owner.check_unpark_now(self.id);
+        #[cfg(parking_tsan)]
+        tsan::release(&self.state);
+        #[cfg(feature = "diagnostics")]
+        self.stats.record_unpark();
+        if !state::notify_parked(&self.state) {
+            return false;
+        }
+        self.wake();
+        true
+    }
+
+    /// Wakes the parked thread after `state` went from `PARKED` to `NOTIFIED`
+    fn wake(&self) {
+        #[cfg(target_vendor = "apple")]
+        if let Some(qos) = &self.qos {
+            qos.boost();
+        }
+        #[cfg(windows)]
+        if let Some(boost) = &self.boost {
+            boost.boost();
+        }
+        self.waiter.unpark(&self.state);
+        #[cfg(feature = "mock-clock")]
+        if let Some(mock) = &self.mock {
+            mock.unpark();
+        }
+        #[cfg(windows)]
+        if let Some(hires) = &self.hires {
+            hires.unpark();
+        }
+        self.record_unpark(true, true);
+    }
+
+    /// `first` is whether this unpark delivered the notification, `slow` whether it had to wake
+    /// a parked thread
+    #[inline]
+    fn record_unpark(&self, first: bool, slow: bool) {
+        if let Some(observer) = observer::get() {
+            observer.on_unpark(self.id, first, slow);
+        }
+        #[cfg(feature = "metrics")]
+        self.metrics.record_unpark(slow);
+        #[cfg(feature = "tracing")]
+        tracing::trace!(parker = self.id, first = first, slow_path = slow, "unpark");
+    }
+
+    fn watch(&self, watcher: Unparker) {
+        *self.watcher.lock().unwrap() = Some(watcher);
+        self.watched.store(true, SeqCst);
+    }
+
+    fn unwatch(&self) {
+        self.watched.store(false, SeqCst);
+        self.watcher.lock().unwrap().take();
+    }
+
+    fn wake_watcher(&self) {
+        // `watch` raises `watched` before `park_any` re-checks `state`, and we only get here after
+        // writing `NOTIFIED` to `state`, so either `park_any` sees the notification or we see the flag
+        if self.watched.load(SeqCst) {
+            if let Some(watcher) = self.watcher.lock().unwrap().as_ref() {
+                watcher.unpark();
+            }
+        }
+    }
+}
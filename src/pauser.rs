@@ -0,0 +1,156 @@
+use std::fmt::Formatter;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::atomic::AtomicBool;
+use crate::{Parker, Unparker};
+
+thread_local! {
+    /// Parks the thread in `Pauser::checkpoint` and `Pauser::wait_paused`, shared by every pauser
+    static PARKER: Parker = Parker::new();
+}
+
+/// Stops workers at checkpoints of their choosing until a controller resumes them, for
+/// stop-the-world work such as taking a snapshot
+///
+/// Workers call `checkpoint` wherever it's safe for them to stop, which costs a single atomic
+/// load while nothing is paused. After `pause`, each worker parks at its next checkpoint, and
+/// the controller waits for as many as it expects with `wait_paused` before doing its work and
+/// calling `resume`:
+///
+/// ```ignore
+/// pauser.pause();
+/// pauser.wait_paused(workers);
+/// take_snapshot();
+/// pauser.resume();
+/// ```
+#[derive(Default)]
+pub struct Pauser {
+    /// Mirrors `State::paused`, so checkpoints skip the lock while nothing is paused
+    requested: AtomicBool,
+    state: Mutex<State>
+}
+
+#[derive(Default)]
+struct State {
+    paused: bool,
+    /// Incremented by every `resume`, which is what parked workers wait for
+    generation: usize,
+    /// Workers parked at a checkpoint
+    parked: Vec<Unparker>,
+    /// Controllers in `wait_paused`, woken by every worker that parks
+    controllers: Vec<Unparker>
+}
+
+impl Pauser {
+
+    pub fn new() -> Pauser {
+        Pauser::default()
+    }
+
+    /// Makes workers park at their next checkpoint, until `resume`
+    pub fn pause(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.paused = true;
+        self.requested.store(true, SeqCst);
+    }
+
+    /// Wakes every worker parked at a checkpoint, and lets the rest pass theirs again
+    pub fn resume(&self) {
+        let parked = {
+            let mut state = self.state.lock().unwrap();
+            if !state.paused {
+                return;
+            }
+            state.paused = false;
+            state.generation = state.generation.wrapping_add(1);
+            self.requested.store(false, SeqCst);
+            std::mem::take(&mut state.parked)
+        };
+        for unparker in parked {
+            unparker.unpark();
+        }
+    }
+
+    /// Return `true` between `pause` and `resume`
+    pub fn is_paused(&self) -> bool {
+        self.requested.load(SeqCst)
+    }
+
+    /// Return the number of workers parked at a checkpoint
+    pub fn paused(&self) -> usize {
+        self.state.lock().unwrap().parked.len()
+    }
+
+    /// Parks the calling worker until `resume` if the pauser is paused, and otherwise returns at
+    /// once
+    ///
+    /// return `true` if the worker was paused
+    pub fn checkpoint(&self) -> bool {
+        if !self.requested.load(SeqCst) {
+            return false;
+        }
+        PARKER.with(|parker| {
+            let (generation, controllers) = {
+                let mut state = self.state.lock().unwrap();
+                if !state.paused {
+                    return false;
+                }
+                state.parked.push(parker.unparker());
+                (state.generation, std::mem::take(&mut state.controllers))
+            };
+            for controller in controllers {
+                controller.unpark();
+            }
+            // The parker is shared with other waits on this thread, whose wakeups land here too
+            loop {
+                parker.park();
+                if self.state.lock().unwrap().generation != generation {
+                    return true;
+                }
+            }
+        })
+    }
+
+    /// Parks the calling controller until at least `count` workers are parked at a checkpoint
+    pub fn wait_paused(&self, count: usize) {
+        self.wait_paused_deadline(count, None);
+    }
+
+    /// Parks the calling controller until at least `count` workers are parked at a checkpoint,
+    /// but at most for `duration`
+    ///
+    /// return `false` if fewer had paused by then
+    pub fn wait_paused_timeout(&self, count: usize, duration: Duration) -> bool {
+        self.wait_paused_deadline(count, Instant::now().checked_add(duration))
+    }
+
+    fn wait_paused_deadline(&self, count: usize, deadline: Option<Instant>) -> bool {
+        PARKER.with(|parker| loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                let done = state.parked.len() >= count;
+                if done || deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    state.controllers.retain(|u| u.id() != parker.id());
+                    return done;
+                }
+                if !state.controllers.iter().any(|u| u.id() == parker.id()) {
+                    state.controllers.push(parker.unparker());
+                }
+            }
+            match deadline {
+                None => parker.park(),
+                Some(deadline) => {
+                    parker.park_deadline(deadline);
+                }
+            }
+        })
+    }
+}
+
+impl std::fmt::Debug for Pauser {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("Pauser { .. }")
+    }
+}
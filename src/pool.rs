@@ -0,0 +1,81 @@
+use std::fmt::Formatter;
+use std::sync::{Arc, Mutex};
+
+use crate::parker::Inner;
+use crate::{Parker, ParkerBuilder};
+
+/// Hands out parkers, reusing the allocations of dropped ones
+///
+/// For workloads that create and drop short-lived parkers at a high rate. A dropped `Parker` goes
+/// back to its pool, keeping its configuration, unless an `Unparker` for it is still alive. It
+/// comes back out with a new `id` and no pending notification, indistinguishable from a new one.
+/// Clones share the same pool.
+#[derive(Clone)]
+pub struct ParkerPool {
+    shared: Arc<Pool>
+}
+
+pub(crate) struct Pool {
+    builder: ParkerBuilder,
+    capacity: usize,
+    idle: Mutex<Vec<Arc<Inner>>>
+}
+
+impl ParkerPool {
+
+    /// Creates a pool of default parkers that keeps at most `capacity` idle ones
+    pub fn new(capacity: usize) -> ParkerPool {
+        ParkerPool::from_builder(ParkerBuilder::new(), capacity)
+    }
+
+    pub(crate) fn from_builder(builder: ParkerBuilder, capacity: usize) -> ParkerPool {
+        ParkerPool {
+            shared: Arc::new(Pool {
+                builder,
+                capacity,
+                idle: Mutex::new(Vec::with_capacity(capacity))
+            })
+        }
+    }
+
+    /// Return a parker, recycled if one is idle
+    pub fn parker(&self) -> Parker {
+        loop {
+            let inner = self.shared.idle.lock().unwrap().pop();
+            let mut inner = match inner {
+                Some(inner) => inner,
+                None => break
+            };
+            // The dropping `Parker` may not have let go of its handle yet, in which case this one
+            // is no good
+            if let Some(recycled) = Arc::get_mut(&mut inner) {
+                recycled.reset();
+                return Parker::from_inner(inner);
+            }
+        }
+        let mut inner = self.shared.builder.clone().build_inner();
+        inner.pool = Some(Arc::downgrade(&self.shared));
+        Parker::from_inner(Arc::new(inner))
+    }
+
+    /// Return the number of idle parkers waiting to be reused
+    pub fn idle(&self) -> usize {
+        self.shared.idle.lock().unwrap().len()
+    }
+}
+
+impl Pool {
+
+    pub(crate) fn recycle(&self, inner: Arc<Inner>) {
+        let mut idle = self.idle.lock().unwrap();
+        if idle.len() < self.capacity {
+            idle.push(inner);
+        }
+    }
+}
+
+impl std::fmt::Debug for ParkerPool {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("ParkerPool { .. }")
+    }
+}
@@ -0,0 +1,80 @@
+//! QoS overrides for parked threads on Apple platforms, see `ParkerBuilder::qos_override`
+
+use std::os::raw::{c_int, c_void};
+use std::sync::Mutex;
+
+extern "C" {
+    fn pthread_get_qos_class_np(thread: libc::pthread_t, class: *mut u32, priority: *mut c_int) -> c_int;
+    fn pthread_override_qos_class_start_np(thread: libc::pthread_t, class: u32, priority: c_int) -> *mut c_void;
+    fn pthread_override_qos_class_end_np(handle: *mut c_void) -> c_int;
+}
+
+/// Return the QoS class of `thread`, where a higher value is a higher class
+fn qos_class(thread: libc::pthread_t) -> u32 {
+    let (mut class, mut priority) = (0, 0);
+    // SAFETY: `thread` is a live thread and both outputs are valid to write to
+    if unsafe { pthread_get_qos_class_np(thread, &mut class, &mut priority) } != 0 {
+        return 0;
+    }
+    class
+}
+
+/// Raises the parked thread to the QoS class of the thread that unparks it
+pub(crate) struct QosOverride {
+    state: Mutex<State>
+}
+
+#[derive(Default)]
+struct State {
+    /// The parked thread, for as long as it's blocked
+    parked: Option<usize>,
+    /// Handle of the override applied to it by an unpark
+    active: Option<usize>
+}
+
+impl QosOverride {
+
+    pub(crate) fn new() -> QosOverride {
+        QosOverride {
+            state: Mutex::new(State::default())
+        }
+    }
+
+    /// Called by the parking thread before it can be seen as `PARKED`
+    pub(crate) fn begin_park(&self) {
+        // SAFETY: always safe to call
+        let thread = unsafe { libc::pthread_self() };
+        self.state.lock().unwrap().parked = Some(thread as usize);
+    }
+
+    /// Called by the parking thread once it has stopped blocking, ending any override
+    pub(crate) fn end_park(&self) {
+        let active = {
+            let mut state = self.state.lock().unwrap();
+            state.parked = None;
+            state.active.take()
+        };
+        if let Some(handle) = active {
+            // SAFETY: `handle` came from `pthread_override_qos_class_start_np` and is ended once
+            unsafe { pthread_override_qos_class_end_np(handle as *mut c_void) };
+        }
+    }
+
+    /// Called by an unparking thread that found the parker `PARKED`, before waking it
+    pub(crate) fn boost(&self) {
+        let mut state = self.state.lock().unwrap();
+        // Once `end_park` takes the lock no override is started, so none outlives the park
+        if let (Some(thread), None) = (state.parked, state.active) {
+            let thread = thread as libc::pthread_t;
+            // SAFETY: always safe to call
+            let class = qos_class(unsafe { libc::pthread_self() });
+            if class > qos_class(thread) {
+                // SAFETY: `thread` is blocked in `park` until `end_park`, which waits for the lock
+                let handle = unsafe { pthread_override_qos_class_start_np(thread, class, 0) };
+                if !handle.is_null() {
+                    state.active = Some(handle as usize);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::fmt::Formatter;
+use std::sync::{Mutex, PoisonError};
+use std::thread::{self, Thread, ThreadId};
+use std::time::{Duration, Instant};
+
+/// Parks currently blocked, by parker id
+///
+/// Every update is a single insert or remove, so a panic under the lock can't leave the map
+/// half-changed, and a poisoned lock is used as is.
+static PARKED: Mutex<Option<HashMap<usize, Entry>>> = Mutex::new(None);
+
+struct Entry {
+    thread: Thread,
+    since: Instant,
+    deadline: Option<Instant>
+}
+
+/// A thread blocked in a park when `dump_parked` was called
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ParkedThread {
+    /// Identifier of the parker, see `Parker::id`
+    pub parker_id: usize,
+    /// Name of the parked thread, if it has one
+    pub thread_name: Option<String>,
+    pub thread_id: ThreadId,
+    /// How long the thread had been blocked
+    pub parked_for: Duration,
+    /// When a timed park times out
+    pub deadline: Option<Instant>
+}
+
+impl std::fmt::Display for ParkedThread {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.thread_name {
+            Some(name) => write!(f, "thread '{}'", name)?,
+            None => write!(f, "thread {:?}", self.thread_id)?
+        }
+        write!(f, " parked on parker {} for {:?}", self.parker_id, self.parked_for)?;
+        if let Some(deadline) = self.deadline {
+            write!(f, ", times out in {:?}", deadline.saturating_duration_since(Instant::now()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Return every thread currently blocked in a park, longest parked first
+///
+/// Only parks that got as far as blocking are listed, not those that consumed a notification
+/// straight away. This takes a lock and allocates, so it can't be called from a signal handler;
+/// have the handler wake a thread that calls it instead.
+pub fn dump_parked() -> Vec<ParkedThread> {
+    let now = Instant::now();
+    let entries = PARKED.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut parked: Vec<_> = entries.iter().flatten().map(|(&parker_id, entry)| ParkedThread {
+        parker_id,
+        thread_name: entry.thread.name().map(str::to_owned),
+        thread_id: entry.thread.id(),
+        parked_for: now.saturating_duration_since(entry.since),
+        deadline: entry.deadline
+    }).collect();
+    drop(entries);
+    parked.sort_by_key(|p| std::cmp::Reverse(p.parked_for));
+    parked
+}
+
+/// Lists the calling thread as blocked on the parker `parker_id` until dropped
+pub(crate) struct Registered(usize);
+
+impl Registered {
+    pub(crate) fn new(parker_id: usize, deadline: Option<Instant>) -> Registered {
+        let entry = Entry { thread: thread::current(), since: Instant::now(), deadline };
+        let mut parked = PARKED.lock().unwrap_or_else(PoisonError::into_inner);
+        parked.get_or_insert_with(HashMap::new).insert(parker_id, entry);
+        Registered(parker_id)
+    }
+}
+
+impl Drop for Registered {
+    fn drop(&mut self) {
+        // Also runs while a park unwinds, which must not turn into a double panic
+        let mut parked = PARKED.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(parked) = parked.as_mut() {
+            parked.remove(&self.0);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt::Formatter;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::Arc;
+use std::thread::{self, ScopedJoinHandle};
+use std::time::Duration;
+
+use crate::atomic::AtomicUsize;
+use crate::{pair, MultiUnparker, Parker, Unparker, UnparkerKey};
+
+/// How often the end of a scope unparks workers that parked again after being woken
+const EXIT_RETRY: Duration = Duration::from_millis(10);
+
+/// Like `std::thread::scope`, but each thread spawned through the `Scope` owns a `Parker`
+/// whose `Unparker` the spawning side keeps
+///
+/// When `f` returns or unwinds, every worker still alive is unparked, and unparked again each
+/// time it parks, until all of them have finished. So a worker blocked on its parker can't hold
+/// up the end of the scope, although it must still notice that its work is over.
+pub fn scope<'env, F, T>(f: F) -> T
+    where F: for<'scope> FnOnce(&Scope<'scope, 'env>) -> T
+{
+    let parent = Parker::new();
+    let shared = Arc::new(Shared {
+        workers: MultiUnparker::new(),
+        live: AtomicUsize::new(0),
+        parent: parent.unparker()
+    });
+    thread::scope(move |scope| {
+        let _exit = WakeOnExit { shared: shared.clone(), parent };
+        f(&Scope { scope, shared })
+    })
+}
+
+/// Spawns threads with a `Parker` each, see `scope`
+pub struct Scope<'scope, 'env: 'scope> {
+    scope: &'scope thread::Scope<'scope, 'env>,
+    shared: Arc<Shared>
+}
+
+/// A thread spawned with `Scope::spawn`
+pub struct ScopedWorker<'scope, T> {
+    handle: ScopedJoinHandle<'scope, T>,
+    unparker: Unparker
+}
+
+struct Shared {
+    /// Workers that haven't finished yet
+    workers: MultiUnparker,
+    live: AtomicUsize,
+    /// Told whenever a worker finishes
+    parent: Unparker
+}
+
+impl<'scope, 'env> Scope<'scope, 'env> {
+
+    /// Spawns a thread that runs `f` with a parker of its own
+    pub fn spawn<F, T>(&self, f: F) -> ScopedWorker<'scope, T>
+        where F: FnOnce(Parker) -> T + Send + 'scope,
+              T: Send + 'scope
+    {
+        let (parker, unparker) = pair();
+        let shared = self.shared.clone();
+        let key = shared.workers.add(unparker.clone());
+        shared.live.fetch_add(1, SeqCst);
+        let handle = self.scope.spawn(move || {
+            let _finished = Finished { shared: &shared, key };
+            f(parker)
+        });
+        ScopedWorker { handle, unparker }
+    }
+
+    /// Notifies every worker that hasn't finished yet
+    ///
+    /// return the number of workers this call was the first to notify
+    pub fn unpark_all(&self) -> usize {
+        self.shared.workers.unpark_all()
+    }
+}
+
+impl<T> ScopedWorker<'_, T> {
+
+    /// Return the handle for unparking the worker
+    pub fn unparker(&self) -> &Unparker {
+        &self.unparker
+    }
+
+    /// Return `true` if the worker has finished running
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Waits for the worker to finish, returning what it returned or the payload it panicked with
+    pub fn join(self) -> thread::Result<T> {
+        self.handle.join()
+    }
+}
+
+/// Unregisters a worker as it finishes, also when it panics
+struct Finished<'a> {
+    shared: &'a Shared,
+    key: UnparkerKey
+}
+
+impl Drop for Finished<'_> {
+    fn drop(&mut self) {
+        self.shared.workers.remove(self.key);
+        self.shared.live.fetch_sub(1, SeqCst);
+        self.shared.parent.unpark();
+    }
+}
+
+/// Keeps unparking workers at the end of the scope, before `std::thread::scope` joins them
+struct WakeOnExit {
+    shared: Arc<Shared>,
+    parent: Parker
+}
+
+impl Drop for WakeOnExit {
+    fn drop(&mut self) {
+        while self.shared.live.load(SeqCst) > 0 {
+            self.shared.workers.unpark_all();
+            self.parent.park_timeout(EXIT_RETRY);
+        }
+    }
+}
+
+impl std::fmt::Debug for Scope<'_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("Scope { .. }")
+    }
+}
+
+impl<T> std::fmt::Debug for ScopedWorker<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("ScopedWorker { .. }")
+    }
+}
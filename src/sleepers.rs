@@ -0,0 +1,132 @@
+use std::fmt::Formatter;
+use std::sync::Mutex;
+
+use crate::Unparker;
+
+/// Tracks which workers of a pool are idle, so that new work wakes exactly one of them
+///
+/// Workers announce themselves with `go_idle` before parking and withdraw with `leave_idle`
+/// after. Producers call `notify_one_idle` after publishing work. A notification that finds no
+/// idle worker is kept, and the next `go_idle` consumes it instead of letting its worker sleep,
+/// so work published while every worker was between its last look and `go_idle` isn't missed.
+///
+/// A worker loop looks like this, with the second look for work closing the window between the
+/// first one and `go_idle`:
+///
+/// ```ignore
+/// loop {
+///     if let Some(job) = queue.pop() {
+///         job();
+///         continue;
+///     }
+///     if !sleepers.go_idle(&unparker) {
+///         continue;
+///     }
+///     if let Some(job) = queue.pop() {
+///         sleepers.leave_idle(&unparker);
+///         job();
+///         continue;
+///     }
+///     parker.park();
+///     sleepers.leave_idle(&unparker);
+/// }
+/// ```
+///
+/// and a producer pushes to `queue` before calling `notify_one_idle`.
+#[derive(Default)]
+pub struct Sleepers {
+    state: Mutex<State>
+}
+
+#[derive(Default)]
+struct State {
+    /// Idle workers, the most recent last so that `notify_one_idle` wakes the one whose cache is
+    /// still warm
+    idle: Vec<Unparker>,
+    /// Set by a notification that found no idle worker
+    pending: bool
+}
+
+impl Sleepers {
+
+    pub fn new() -> Sleepers {
+        Sleepers::default()
+    }
+
+    /// Marks the worker behind `unparker` idle, before it takes a last look for work and parks
+    ///
+    /// return `false`, without marking it, if a notification arrived while no worker was idle.
+    /// The worker should then look for work again instead of parking.
+    pub fn go_idle(&self, unparker: &Unparker) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.pending {
+            state.pending = false;
+            return false;
+        }
+        if !state.idle.iter().any(|u| u.id() == unparker.id()) {
+            state.idle.push(unparker.clone());
+        }
+        true
+    }
+
+    /// Withdraws the worker behind `unparker` after it woke up or found work
+    ///
+    /// return `true` if it was still idle, or `false` if a notification picked it in the
+    /// meantime. That notification is then pending on its parker and makes the next park return
+    /// at once, which the worker loop absorbs by looking for work.
+    pub fn leave_idle(&self, unparker: &Unparker) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.idle.iter().position(|u| u.id() == unparker.id()) {
+            Some(i) => {
+                state.idle.remove(i);
+                true
+            }
+            None => false
+        }
+    }
+
+    /// Wakes one idle worker, or keeps the notification for the next `go_idle` if none is idle
+    ///
+    /// return `true` if a worker was woken
+    pub fn notify_one_idle(&self) -> bool {
+        let unparker = {
+            let mut state = self.state.lock().unwrap();
+            match state.idle.pop() {
+                Some(unparker) => unparker,
+                None => {
+                    state.pending = true;
+                    return false;
+                }
+            }
+        };
+        // Out of the lock, so the worker doesn't wake up only to block on it in `leave_idle`
+        unparker.unpark();
+        true
+    }
+
+    /// Wakes every idle worker
+    ///
+    /// return the number of workers woken. Unlike `notify_one_idle`, nothing is kept when none
+    /// is idle.
+    pub fn notify_all_idle(&self) -> usize {
+        let idle = std::mem::take(&mut self.state.lock().unwrap().idle);
+        idle.iter().for_each(|u| { u.unpark(); });
+        idle.len()
+    }
+
+    /// Return the number of idle workers
+    pub fn idle(&self) -> usize {
+        self.state.lock().unwrap().idle.len()
+    }
+
+    /// Return `true` if a notification is waiting for the next `go_idle`
+    pub fn is_pending(&self) -> bool {
+        self.state.lock().unwrap().pending
+    }
+}
+
+impl std::fmt::Debug for Sleepers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("Sleepers { .. }")
+    }
+}
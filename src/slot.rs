@@ -0,0 +1,124 @@
+//! A lock-free slot holding the unparker of whoever waits next, like `futures`' `AtomicWaker`
+//!
+//! The slot has a state word next to it. `register` moves it to `REGISTERING` while it writes
+//! the slot, `take` and `wake` set `WAKING` while they empty it, and whoever finds the other one
+//! in progress leaves the slot alone. A `wake` landing while a `register` writes can't empty the
+//! slot, so it leaves `WAKING` set for the register, which then unparks the new unparker itself
+//! before returning. Either way, a wake that comes after a register started isn't lost.
+
+use std::cell::UnsafeCell;
+use std::fmt::Formatter;
+use std::sync::atomic::Ordering::{AcqRel, Acquire, Release};
+
+use crate::atomic::AtomicUsize;
+use crate::Unparker;
+
+/// Nobody is using the slot
+const WAITING: usize = 0;
+/// A `register` call is writing the slot
+const REGISTERING: usize = 0b01;
+/// A `take` or `wake` call is emptying the slot, or asked the register writing it to
+const WAKING: usize = 0b10;
+
+/// A slot for the unparker of the thread waiting on something, which whoever makes that
+/// something happen takes out and unparks
+///
+/// The waiting thread registers before checking its condition, and parks only if the condition
+/// doesn't hold yet:
+///
+/// ```ignore
+/// loop {
+///     slot.register(&parker.unparker());
+///     if ready.load(SeqCst) {
+///         break;
+///     }
+///     parker.park();
+/// }
+/// ```
+///
+/// The other side makes the condition hold and then calls `wake`. Registering replaces the
+/// unparker there, so the slot is for one waiter at a time. Of two threads registering at once,
+/// one wins and the other's unparker isn't stored.
+#[derive(Default)]
+pub struct AtomicUnparker {
+    state: AtomicUsize,
+    unparker: UnsafeCell<Option<Unparker>>
+}
+
+// SAFETY: the slot is only accessed by whoever moved `state` away from `WAITING`
+unsafe impl Send for AtomicUnparker {}
+unsafe impl Sync for AtomicUnparker {}
+
+impl AtomicUnparker {
+
+    pub fn new() -> AtomicUnparker {
+        AtomicUnparker::default()
+    }
+
+    /// Stores a clone of `unparker` for the next `wake`, replacing the one there
+    ///
+    /// Storing an unparker for the same parker as the one there keeps that one. If a `wake` is
+    /// in progress, `unparker` is unparked instead, so the park after this returns at once.
+    pub fn register(&self, unparker: &Unparker) {
+        match self.state.compare_exchange(WAITING, REGISTERING, Acquire, Acquire).unwrap_or_else(|s| s) {
+            WAITING => {
+                // SAFETY: `REGISTERING` gives us the slot, `take` and `wake` leave it alone
+                let slot = unsafe { &mut *self.unparker.get() };
+                let old = match slot {
+                    Some(current) if current.id() == unparker.id() => None,
+                    _ => slot.replace(unparker.clone())
+                };
+                if let Err(state) = self.state.compare_exchange(REGISTERING, WAITING, AcqRel, Acquire) {
+                    // A wake came in while we wrote the slot, and left emptying it to us
+                    debug_assert_eq!(state, REGISTERING | WAKING);
+                    let current = slot.take();
+                    self.state.swap(WAITING, AcqRel);
+                    if let Some(current) = current {
+                        current.unpark();
+                    }
+                }
+                // Dropped once the slot is released, as dropping a bridged unparker may run
+                // arbitrary code
+                drop(old);
+            }
+            WAKING => {
+                // The wake in progress may have taken the previous unparker, or none at all
+                unparker.unpark();
+            }
+            state => {
+                // Another register is writing the slot, and wins
+                debug_assert!(state == REGISTERING || state == REGISTERING | WAKING);
+            }
+        }
+    }
+
+    /// Takes the registered unparker out of the slot
+    ///
+    /// return `None` if there is none, or if a `register` or `wake` is in progress, which then
+    /// sees to the wakeup
+    pub fn take(&self) -> Option<Unparker> {
+        match self.state.fetch_or(WAKING, AcqRel) {
+            WAITING => {
+                // SAFETY: `WAKING` gives us the slot, `register` leaves it alone
+                let unparker = unsafe { (*self.unparker.get()).take() };
+                self.state.fetch_and(!WAKING, Release);
+                unparker
+            }
+            _ => None
+        }
+    }
+
+    /// Takes the registered unparker out of the slot and unparks it
+    ///
+    /// return `true` if this call was the first to notify the parker since it last parked. A
+    /// wake during a `register` returns `false` and leaves the unpark to it.
+    pub fn wake(&self) -> bool {
+        self.take().is_some_and(|unparker| unparker.unpark())
+    }
+}
+
+impl std::fmt::Debug for AtomicUnparker {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("AtomicUnparker { .. }")
+    }
+}
@@ -0,0 +1,104 @@
+//! Low-power polling for the spin phase of a park
+//!
+//! Rather than burning cycles in `spin_loop`, `wait_while` lets the core idle until the state
+//! word is written, where the CPU can watch a cache line: `UMONITOR`/`UMWAIT` on x86-64 CPUs
+//! with WAITPKG, detected at run time, and the exclusive monitor plus `WFE` on aarch64 Linux.
+//! Unparking writes the state word anyway, so it needs no `SEV` or other extra step.
+
+use crate::atomic::AtomicU32;
+
+/// Waits briefly, or until `word` no longer holds `current`, whichever comes first. May also
+/// return early for other reasons.
+pub(crate) fn wait_while(word: &AtomicU32, current: u32) {
+    imp::wait_while(word, current)
+}
+
+#[cfg(target_arch = "x86_64")]
+mod imp {
+    use std::arch::asm;
+    use std::arch::x86_64::{__cpuid, __cpuid_count, _rdtsc};
+    use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+    use std::sync::atomic::AtomicU8;
+
+    use crate::atomic::AtomicU32;
+
+    /// TSC ticks a single `UMWAIT` lasts at most, around a microsecond
+    const UMWAIT_TICKS: u64 = 4096;
+    /// `UMWAIT` control selecting C0.1, the lighter state that wakes faster
+    const C0_1: u32 = 1;
+
+    const UNKNOWN: u8 = 0;
+    const ABSENT: u8 = 1;
+    const PRESENT: u8 = 2;
+
+    static WAITPKG: AtomicU8 = AtomicU8::new(UNKNOWN);
+
+    #[allow(unused_unsafe)]
+    fn detect_waitpkg() -> bool {
+        // SAFETY: every x86-64 CPU has `cpuid`, and leaf 7 is only read if it exists
+        unsafe { __cpuid(0).eax >= 7 && __cpuid_count(7, 0).ecx & (1 << 5) != 0 }
+    }
+
+    fn has_waitpkg() -> bool {
+        match WAITPKG.load(Relaxed) {
+            UNKNOWN => {
+                let present = detect_waitpkg();
+                WAITPKG.store(if present { PRESENT } else { ABSENT }, Relaxed);
+                present
+            }
+            state => state == PRESENT
+        }
+    }
+
+    #[allow(unused_unsafe)]
+    pub(super) fn wait_while(word: &AtomicU32, current: u32) {
+        if !has_waitpkg() {
+            std::hint::spin_loop();
+            return;
+        }
+        // SAFETY: reads the time stamp counter
+        let deadline = unsafe { _rdtsc() }.wrapping_add(UMWAIT_TICKS);
+        // SAFETY: WAITPKG is present. `umonitor` only arms the monitor on the line of `word`, so
+        // that a write to it ends the `umwait`, which otherwise ends at `deadline` at the latest.
+        unsafe {
+            asm!("umonitor {}", in(reg) word.as_ptr(), options(nostack, preserves_flags));
+            if word.load(SeqCst) == current {
+                asm!(
+                    "umwait {:e}",
+                    in(reg) C0_1,
+                    in("eax") deadline as u32,
+                    in("edx") (deadline >> 32) as u32,
+                    options(nostack)
+                );
+            }
+        }
+    }
+}
+
+#[cfg(all(target_arch = "aarch64", any(target_os = "linux", target_os = "android")))]
+mod imp {
+    use std::arch::asm;
+    use crate::atomic::AtomicU32;
+
+    pub(super) fn wait_while(word: &AtomicU32, current: u32) {
+        let value: u32;
+        // SAFETY: `ldxr` reads `word`, which is valid and aligned, and arms the exclusive monitor
+        // so that a write to it by another core ends the `wfe`. The kernel's timer event stream
+        // ends it within about 100 microseconds otherwise.
+        unsafe {
+            asm!("ldxr {:w}, [{}]", out(reg) value, in(reg) word.as_ptr(), options(nostack, preserves_flags, readonly));
+            if value == current {
+                asm!("wfe", options(nomem, nostack, preserves_flags));
+            }
+        }
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", all(target_arch = "aarch64", any(target_os = "linux", target_os = "android")))))]
+mod imp {
+    use crate::atomic::AtomicU32;
+
+    pub(super) fn wait_while(_word: &AtomicU32, _current: u32) {
+        std::hint::spin_loop();
+    }
+}
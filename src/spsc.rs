@@ -0,0 +1,292 @@
+//! A bounded single-producer, single-consumer ring buffer, where `push` parks the producer while
+//! it's full and `pop` parks the consumer while it's empty
+//!
+//! Each side has a parker of its own and raises a flag before parking on it. The other side only
+//! unparks after seeing the flag, so while both keep up, moving an element is a couple of
+//! atomic operations, and the wakeups cost a syscall only once a side actually had to wait.
+//!
+//! ```ignore
+//! let (mut producer, mut consumer) = parking::spsc::ring(1024);
+//! thread::spawn(move || {
+//!     for frame in frames {
+//!         producer.push(frame).unwrap();
+//!     }
+//! });
+//! while let Ok(frame) = consumer.pop() {
+//!     play(frame);
+//! }
+//! ```
+
+use std::cell::UnsafeCell;
+use std::fmt::Formatter;
+use std::mem::MaybeUninit;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, SeqCst};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::atomic::{AtomicBool, AtomicUsize};
+use crate::mpsc::{RecvError, RecvTimeoutError, SendError, TryRecvError, TrySendError};
+use crate::pad::CachePadded;
+use crate::{Parker, Unparker};
+
+/// Return the two ends of a ring holding at most `capacity` elements
+///
+/// Panics if `capacity` is zero.
+pub fn ring<T>(capacity: usize) -> (Producer<T>, Consumer<T>) {
+    assert!(capacity > 0, "a ring needs a capacity of at least one");
+    let producer = Parker::new();
+    let consumer = Parker::new();
+    let shared = Arc::new(Shared {
+        head: CachePadded::new(AtomicUsize::new(0)),
+        tail: CachePadded::new(AtomicUsize::new(0)),
+        producer_waiting: CachePadded::new(AtomicBool::new(false)),
+        consumer_waiting: CachePadded::new(AtomicBool::new(false)),
+        closed: AtomicBool::new(false),
+        producer: producer.unparker(),
+        consumer: consumer.unparker(),
+        slots: (0..capacity).map(|_| UnsafeCell::new(MaybeUninit::uninit())).collect()
+    });
+    (
+        Producer { shared: shared.clone(), parker: producer },
+        Consumer { shared, parker: consumer }
+    )
+}
+
+/// The pushing end of a `ring`
+pub struct Producer<T> {
+    shared: Arc<Shared<T>>,
+    /// Parked on while the ring is full
+    parker: Parker
+}
+
+/// The popping end of a `ring`
+pub struct Consumer<T> {
+    shared: Arc<Shared<T>>,
+    /// Parked on while the ring is empty
+    parker: Parker
+}
+
+/// Indices count pushes and pops from the start, wrapping around, and are taken modulo the
+/// capacity for a slot. The ring holds `tail - head` elements.
+struct Shared<T> {
+    /// Next slot to pop, only written by the consumer
+    head: CachePadded<AtomicUsize>,
+    /// Next slot to push, only written by the producer
+    tail: CachePadded<AtomicUsize>,
+    /// Raised by the producer before parking on a full ring
+    producer_waiting: CachePadded<AtomicBool>,
+    /// Raised by the consumer before parking on an empty ring
+    consumer_waiting: CachePadded<AtomicBool>,
+    /// Set when either end is dropped
+    closed: AtomicBool,
+    producer: Unparker,
+    consumer: Unparker,
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>
+}
+
+// SAFETY: a slot is only accessed by the producer between the consumer's `head` and its own
+// `tail`, and by the consumer outside of that, with `head` and `tail` handing elements over
+unsafe impl<T: Send> Send for Shared<T> {}
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+
+    fn slot(&self, index: usize) -> *mut MaybeUninit<T> {
+        self.slots[index % self.slots.len()].get()
+    }
+
+    /// `SeqCst`, as `wait` checks this right after raising its flag
+    fn len(&self) -> usize {
+        self.tail.load(SeqCst).wrapping_sub(self.head.load(SeqCst))
+    }
+
+    /// Parks on `parker` until `ready` or `closed`, or until `deadline`
+    ///
+    /// return `false` if the deadline passed first
+    fn wait(&self, waiting: &AtomicBool, parker: &Parker, deadline: Option<Instant>, ready: impl Fn() -> bool) -> bool {
+        waiting.store(true, SeqCst);
+        let mut timed_out = false;
+        while !ready() && !self.closed.load(SeqCst) {
+            match deadline {
+                None => parker.park(),
+                Some(deadline) => {
+                    if !parker.park_deadline(deadline) && Instant::now() >= deadline {
+                        timed_out = true;
+                        break;
+                    }
+                }
+            }
+            // A wakeup lowers the flag, and ending the wait would leave it lowered
+            waiting.store(true, SeqCst);
+        }
+        waiting.store(false, SeqCst);
+        !timed_out || ready()
+    }
+
+    fn close(&self) {
+        self.closed.store(true, SeqCst);
+        self.producer.unpark();
+        self.consumer.unpark();
+    }
+}
+
+/// Unparks the other end if it's waiting on `waiting`
+///
+/// The `SeqCst` store of the index before this pairs with the `SeqCst` load of it after the
+/// other end raises `waiting`, so either it sees the change or we see the flag.
+fn wake(waiting: &AtomicBool, unparker: &Unparker) {
+    if waiting.load(SeqCst) && waiting.swap(false, SeqCst) {
+        unparker.unpark();
+    }
+}
+
+impl<T> Producer<T> {
+
+    /// Pushes `t`, parking while the ring is full
+    ///
+    /// Fails, handing `t` back, if the consumer was dropped, also while waiting for room.
+    pub fn push(&mut self, mut t: T) -> Result<(), SendError<T>> {
+        loop {
+            t = match self.try_push(t) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Disconnected(t)) => return Err(SendError(t)),
+                Err(TrySendError::Full(t)) => t
+            };
+            let shared = &*self.shared;
+            shared.wait(&shared.producer_waiting, &self.parker, None, || shared.len() < shared.slots.len());
+        }
+    }
+
+    /// Pushes `t` if the ring has room, without blocking
+    pub fn try_push(&mut self, t: T) -> Result<(), TrySendError<T>> {
+        let shared = &*self.shared;
+        if shared.closed.load(SeqCst) {
+            return Err(TrySendError::Disconnected(t));
+        }
+        let tail = shared.tail.load(Relaxed);
+        if tail.wrapping_sub(shared.head.load(Acquire)) >= shared.slots.len() {
+            return Err(TrySendError::Full(t));
+        }
+        // SAFETY: the slot at `tail` is free, as the consumer is done with everything before
+        // `head`, and stays ours until `tail` moves past it
+        unsafe { (*shared.slot(tail)).write(t) };
+        shared.tail.store(tail.wrapping_add(1), SeqCst);
+        wake(&shared.consumer_waiting, &shared.consumer);
+        Ok(())
+    }
+
+    /// Return the number of elements in the ring
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    /// Return `true` if the ring holds no elements
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the number of elements the ring holds at most
+    pub fn capacity(&self) -> usize {
+        self.shared.slots.len()
+    }
+}
+
+impl<T> Consumer<T> {
+
+    /// Pops the oldest element, parking while the ring is empty
+    ///
+    /// Fails once the producer is gone and the ring is empty.
+    pub fn pop(&mut self) -> Result<T, RecvError> {
+        self.pop_deadline(None).map_err(|_| RecvError)
+    }
+
+    /// Pops the oldest element, parking while the ring is empty, but at most for `duration`
+    pub fn pop_timeout(&mut self, duration: Duration) -> Result<T, RecvTimeoutError> {
+        self.pop_deadline(Instant::now().checked_add(duration))
+    }
+
+    /// Pops the oldest element if there is one, without blocking
+    pub fn try_pop(&mut self) -> Result<T, TryRecvError> {
+        let shared = &*self.shared;
+        let head = shared.head.load(Relaxed);
+        if shared.tail.load(Acquire) == head {
+            // The producer may have pushed a last element before it was dropped
+            return if shared.closed.load(SeqCst) && shared.tail.load(Acquire) == head {
+                Err(TryRecvError::Disconnected)
+            } else {
+                Err(TryRecvError::Empty)
+            };
+        }
+        // SAFETY: the slot at `head` was written before `tail` moved past it, and stays ours
+        // until `head` does
+        let t = unsafe { (*shared.slot(head)).assume_init_read() };
+        shared.head.store(head.wrapping_add(1), SeqCst);
+        wake(&shared.producer_waiting, &shared.producer);
+        Ok(t)
+    }
+
+    /// Return the number of elements in the ring
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    /// Return `true` if the ring holds no elements
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the number of elements the ring holds at most
+    pub fn capacity(&self) -> usize {
+        self.shared.slots.len()
+    }
+
+    fn pop_deadline(&mut self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        loop {
+            match self.try_pop() {
+                Ok(t) => return Ok(t),
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+                Err(TryRecvError::Empty) => {}
+            }
+            let shared = &*self.shared;
+            if !shared.wait(&shared.consumer_waiting, &self.parker, deadline, || shared.len() > 0) {
+                return Err(RecvTimeoutError::Timeout);
+            }
+        }
+    }
+}
+
+impl<T> Drop for Producer<T> {
+    fn drop(&mut self) {
+        self.shared.close();
+    }
+}
+
+impl<T> Drop for Consumer<T> {
+    fn drop(&mut self) {
+        self.shared.close();
+    }
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        let (head, tail) = (*self.head.get_mut(), *self.tail.get_mut());
+        let mut index = head;
+        while index != tail {
+            // SAFETY: the slots from `head` to `tail` hold pushed elements nobody popped
+            unsafe { (*self.slot(index)).assume_init_drop() };
+            index = index.wrapping_add(1);
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for Producer<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("Producer { .. }")
+    }
+}
+
+impl<T> std::fmt::Debug for Consumer<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("Consumer { .. }")
+    }
+}
@@ -0,0 +1,66 @@
+//! The notification state machine every parker built on a state word shares, free of `std` so
+//! that `nostd::Parker` runs it too
+//!
+//! A parker is `EMPTY`, `PARKED` while its thread blocks, or `NOTIFIED` with a notification the
+//! next park consumes. Only the parking thread moves it to `PARKED` or back to `EMPTY`, and every
+//! unpark moves it to `NOTIFIED`.
+
+use core::sync::atomic::Ordering::SeqCst;
+
+use crate::atomic::AtomicU32;
+
+pub(crate) const EMPTY: u32 = 0;
+pub(crate) const PARKED: u32 = 1;
+pub(crate) const NOTIFIED: u32 = 2;
+
+/// Consumes a pending notification without blocking
+#[inline]
+pub(crate) fn try_consume(state: &AtomicU32) -> bool {
+    state.compare_exchange(NOTIFIED, EMPTY, SeqCst, SeqCst).is_ok()
+}
+
+/// Moves to `PARKED` before blocking
+///
+/// return `false` after consuming a pending notification instead, when there's no need to block
+pub(crate) fn begin_park(state: &AtomicU32) -> bool {
+    match state.compare_exchange(EMPTY, PARKED, SeqCst, SeqCst) {
+        Ok(_) => true,
+        Err(NOTIFIED) => {
+            state.store(EMPTY, SeqCst);
+            false
+        }
+        Err(n) => panic!("inconsistent park state: {}", n)
+    }
+}
+
+/// Moves back to `EMPTY` after blocking since `begin_park`
+///
+/// return `true` if notified in the meantime
+pub(crate) fn end_park(state: &AtomicU32) -> bool {
+    match state.swap(EMPTY, SeqCst) {
+        NOTIFIED => true,
+        PARKED => false,
+        n => panic!("inconsistent park state: {}", n)
+    }
+}
+
+/// Delivers a notification
+///
+/// return the previous state: `PARKED` if the parked thread must be woken
+#[inline]
+pub(crate) fn notify(state: &AtomicU32) -> u32 {
+    // To ensure the unparked thread will observe any writes we made before this call, we must
+    // perform a release operation that `park` can synchronize with. To do that we must write
+    // `NOTIFIED` even if `state` is already `NOTIFIED`. That is why this must be a swap rather
+    // than a compare-and-swap that returns if it reads `NOTIFIED` on failure.
+    state.swap(NOTIFIED, SeqCst)
+}
+
+/// Delivers a notification only if the parked thread is blocked, leaving the state alone
+/// otherwise
+///
+/// return `true` if the parked thread must be woken
+#[inline]
+pub(crate) fn notify_parked(state: &AtomicU32) -> bool {
+    state.compare_exchange(PARKED, NOTIFIED, SeqCst, SeqCst).is_ok()
+}
@@ -0,0 +1,210 @@
+use std::convert::TryFrom;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::atomic::AtomicU64;
+use crate::pad::CachePadded;
+
+/// Bits of a value kept below its leading one, giving eight buckets per power of two and
+/// values within 12.5% of the bucket they land in
+const SUB_BITS: u32 = 3;
+const SUB_BUCKETS: usize = 1 << SUB_BITS;
+/// Values are capped just below 2^40 ns, about 18 minutes
+const MAX_BITS: u32 = 40;
+const BUCKETS: usize = SUB_BUCKETS + (MAX_BITS - SUB_BITS) as usize * SUB_BUCKETS;
+
+/// Timestamps for unpark-to-wakeup latency are taken relative to this
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+
+/// Snapshot of a parker's wait-time distributions, see `Parker::stats`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParkStats {
+    /// Time spent in each park, from the call to its return, including parks that returned at
+    /// once
+    pub parked: Histogram,
+    /// Time from the unpark that woke a blocked park to the parked thread running again
+    pub wakeup_latency: Histogram
+}
+
+/// A distribution of durations, bucketed HDR-style: eight buckets per power of two of
+/// nanoseconds, so each bucket spans at most 12.5% of its values
+#[derive(Clone, PartialEq, Eq)]
+pub struct Histogram {
+    counts: Vec<u64>,
+    count: u64,
+    sum: u64,
+    max: u64
+}
+
+impl Histogram {
+
+    /// Return the number of recorded durations
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Return the longest recorded duration, or zero if none were recorded
+    pub fn max(&self) -> Duration {
+        Duration::from_nanos(self.max)
+    }
+
+    /// Return the average duration, or zero if none were recorded
+    pub fn mean(&self) -> Duration {
+        Duration::from_nanos(self.sum.checked_div(self.count).unwrap_or(0))
+    }
+
+    /// Return the duration that `quantile` of the recorded durations don't exceed, e.g. 0.99 for
+    /// the 99th percentile, rounded up to the end of its bucket and never above `max`
+    pub fn quantile(&self, quantile: f64) -> Duration {
+        if self.count == 0 {
+            return Duration::from_nanos(0);
+        }
+        let rank = ((quantile.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, &n) in self.counts.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                return Duration::from_nanos(bucket_upper(i).min(self.max));
+            }
+        }
+        self.max()
+    }
+
+    /// Return the non-empty buckets in increasing order, as the range of durations each covers
+    /// and the number of durations recorded in it
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, Duration, u64)> + '_ {
+        self.counts.iter().enumerate().filter(|&(_, &n)| n > 0).map(|(i, &n)| {
+            (Duration::from_nanos(bucket_lower(i)), Duration::from_nanos(bucket_upper(i)), n)
+        })
+    }
+}
+
+impl std::fmt::Debug for Histogram {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Histogram")
+            .field("count", &self.count)
+            .field("mean", &self.mean())
+            .field("p50", &self.quantile(0.5))
+            .field("p99", &self.quantile(0.99))
+            .field("max", &self.max())
+            .finish()
+    }
+}
+
+fn bucket(nanos: u64) -> usize {
+    let nanos = nanos.min((1 << MAX_BITS) - 1);
+    if nanos < SUB_BUCKETS as u64 {
+        return nanos as usize;
+    }
+    let exp = 63 - nanos.leading_zeros();
+    let sub = (nanos >> (exp - SUB_BITS)) as usize & (SUB_BUCKETS - 1);
+    (exp - SUB_BITS + 1) as usize * SUB_BUCKETS + sub
+}
+
+fn bucket_lower(i: usize) -> u64 {
+    if i < SUB_BUCKETS {
+        return i as u64;
+    }
+    let exp = (i / SUB_BUCKETS) as u32 - 1 + SUB_BITS;
+    ((SUB_BUCKETS + i % SUB_BUCKETS) as u64) << (exp - SUB_BITS)
+}
+
+fn bucket_upper(i: usize) -> u64 {
+    if i + 1 < BUCKETS { bucket_lower(i + 1) - 1 } else { (1 << MAX_BITS) - 1 }
+}
+
+/// Lock-free recording side of a `Histogram`
+struct Recorder {
+    counts: Box<[AtomicU64]>,
+    count: AtomicU64,
+    sum: AtomicU64,
+    max: AtomicU64
+}
+
+impl Recorder {
+
+    fn new() -> Recorder {
+        Recorder {
+            counts: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+            count: AtomicU64::new(0),
+            sum: AtomicU64::new(0),
+            max: AtomicU64::new(0)
+        }
+    }
+
+    fn record(&self, duration: Duration) {
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        self.counts[bucket(nanos)].fetch_add(1, Relaxed);
+        self.count.fetch_add(1, Relaxed);
+        self.sum.fetch_add(nanos, Relaxed);
+        self.max.fetch_max(nanos, Relaxed);
+    }
+
+    fn snapshot(&self) -> Histogram {
+        Histogram {
+            counts: self.counts.iter().map(|n| n.load(Relaxed)).collect(),
+            count: self.count.load(Relaxed),
+            sum: self.sum.load(Relaxed),
+            max: self.max.load(Relaxed)
+        }
+    }
+}
+
+/// A parker's histograms, plus the time of the latest unpark to measure wakeups against
+pub(crate) struct Stats {
+    parked: Recorder,
+    wakeup_latency: Recorder,
+    /// Nanoseconds since `EPOCH` plus one, or zero before the first unpark. Written by
+    /// unparkers, so kept off the lines the parking thread records on.
+    unparked_at: CachePadded<AtomicU64>
+}
+
+impl Stats {
+
+    pub(crate) fn new() -> Stats {
+        Stats {
+            parked: Recorder::new(),
+            wakeup_latency: Recorder::new(),
+            unparked_at: CachePadded::new(AtomicU64::new(0))
+        }
+    }
+
+    pub(crate) fn record_park(&self, elapsed: Duration) {
+        self.parked.record(elapsed);
+    }
+
+    /// Called by unparkers before they write `NOTIFIED`, so that a woken thread sees the time
+    pub(crate) fn record_unpark(&self) {
+        let epoch = *EPOCH.get_or_init(Instant::now);
+        let nanos = u64::try_from(epoch.elapsed().as_nanos()).unwrap_or(u64::MAX - 1);
+        self.unparked_at.store(nanos + 1, Relaxed);
+    }
+
+    /// Called by the parked thread once a notification ended a wait that began `blocked_since`
+    ///
+    /// Backends also return with a notification that came in before they blocked, so an unpark
+    /// older than `blocked_since` is no wakeup and isn't counted.
+    pub(crate) fn record_wakeup(&self, blocked_since: Instant) {
+        let at = self.unparked_at.load(Relaxed);
+        if let (Some(at), Some(&epoch)) = (at.checked_sub(1), EPOCH.get()) {
+            let at = epoch + Duration::from_nanos(at);
+            if at >= blocked_since {
+                self.wakeup_latency.record(at.elapsed());
+            }
+        }
+    }
+
+    /// Return the time of the latest unpark, or `None` before the first
+    pub(crate) fn last_unpark(&self) -> Option<Instant> {
+        let at = self.unparked_at.load(Relaxed).checked_sub(1)?;
+        Some(*EPOCH.get()? + Duration::from_nanos(at))
+    }
+
+    pub(crate) fn snapshot(&self) -> ParkStats {
+        ParkStats {
+            parked: self.parked.snapshot(),
+            wakeup_latency: self.wakeup_latency.snapshot()
+        }
+    }
+}
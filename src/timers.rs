@@ -0,0 +1,253 @@
+//! Deadlines that unpark a parker when they pass, driven by a thread that parks until the next
+//! one
+//!
+//! Any thread can `insert` a deadline along with the `Unparker` to notify, and a driver thread
+//! loops on `park_until_next_timer`, which parks until the earliest deadline, fires it, and goes
+//! again. A deadline inserted ahead of the one the driver is parked for wakes the driver, so it
+//! never oversleeps. Instead of a dedicated thread, a scheduler can also drive the timers from
+//! its own park, with `next_deadline` for the timeout and `fire_due` afterwards.
+//!
+//! ```ignore
+//! let timers = Arc::new(Timers::new());
+//! let driver = {
+//!     let timers = timers.clone();
+//!     thread::spawn(move || {
+//!         let parker = Parker::new();
+//!         loop {
+//!             timers.park_until_next_timer(&parker);
+//!         }
+//!     })
+//! };
+//!
+//! let (parker, unparker) = parking::pair();
+//! timers.insert_after(Duration::from_millis(10), unparker);
+//! parker.park();
+//! ```
+//!
+//! `Unparker::unpark_at` and `Unparker::unpark_after` go through a process-wide set of timers
+//! instead, driven by a background thread started on first use.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt::Formatter;
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{Parker, Unparker};
+
+/// A set of deadlines, each notifying an `Unparker` once it passes
+#[derive(Default)]
+pub struct Timers {
+    state: Mutex<State>
+}
+
+/// Identifies a deadline inserted into `Timers`, for cancelling it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimerKey(u64);
+
+/// A future unpark scheduled with `Unparker::unpark_at` or `Unparker::unpark_after`
+///
+/// Dropping this leaves the unpark scheduled.
+#[derive(Debug)]
+pub struct ScheduledUnpark {
+    key: TimerKey
+}
+
+impl ScheduledUnpark {
+
+    pub(crate) fn new(key: TimerKey) -> ScheduledUnpark {
+        ScheduledUnpark { key }
+    }
+
+    /// Cancels the unpark
+    ///
+    /// return `true` if it hadn't happened yet
+    pub fn cancel(self) -> bool {
+        global().cancel(self.key)
+    }
+}
+
+/// Return the process-wide timers, starting the thread that drives them on first use
+pub(crate) fn global() -> &'static Timers {
+    static GLOBAL: OnceLock<Timers> = OnceLock::new();
+    let mut started = false;
+    let timers = GLOBAL.get_or_init(|| {
+        started = true;
+        Timers::new()
+    });
+    if started {
+        thread::Builder::new()
+            .name("parking-timers".into())
+            .spawn(move || {
+                let parker = Parker::new();
+                loop {
+                    timers.park_until_next_timer(&parker);
+                }
+            })
+            .expect("failed to spawn the timer thread");
+    }
+    timers
+}
+
+#[derive(Default)]
+struct State {
+    /// Earliest deadline on top. Cancelled timers stay until they surface, and are skipped then,
+    /// or until `cancel` finds them to be most of the heap.
+    heap: BinaryHeap<Reverse<(Instant, u64)>>,
+    /// Timers not yet fired or cancelled, including those too far out for the heap
+    pending: HashMap<u64, Unparker>,
+    next_key: u64,
+    /// The parker of the thread in `park_until_next_timer`, woken by earlier deadlines
+    driver: Option<Unparker>
+}
+
+impl State {
+    /// Drops cancelled timers off the top of the heap
+    fn next_deadline(&mut self) -> Option<Instant> {
+        while let Some(&Reverse((deadline, key))) = self.heap.peek() {
+            if self.pending.contains_key(&key) {
+                return Some(deadline);
+            }
+            self.heap.pop();
+        }
+        None
+    }
+
+    /// Removes every timer due by `now`
+    fn take_due(&mut self, now: Instant) -> Vec<Unparker> {
+        let mut due = Vec::new();
+        while let Some(&Reverse((deadline, key))) = self.heap.peek() {
+            if deadline > now {
+                break;
+            }
+            self.heap.pop();
+            due.extend(self.pending.remove(&key));
+        }
+        due
+    }
+}
+
+impl Timers {
+
+    pub fn new() -> Timers {
+        Timers::default()
+    }
+
+    /// Notifies `unparker` once `deadline` has passed
+    pub fn insert(&self, deadline: Instant, unparker: Unparker) -> TimerKey {
+        let (key, driver) = {
+            let mut state = self.state.lock().unwrap();
+            let key = state.next_key;
+            state.next_key += 1;
+            // The driver is parked for the current earliest deadline at the latest
+            let earliest = state.next_deadline().is_none_or(|next| deadline < next);
+            state.heap.push(Reverse((deadline, key)));
+            state.pending.insert(key, unparker);
+            (key, if earliest { state.driver.clone() } else { None })
+        };
+        if let Some(driver) = driver {
+            driver.unpark();
+        }
+        TimerKey(key)
+    }
+
+    /// Notifies `unparker` once `duration` has passed
+    ///
+    /// A `duration` too long for a deadline to be computed, such as `Duration::MAX`, never fires,
+    /// but counts as pending until cancelled.
+    pub fn insert_after(&self, duration: Duration, unparker: Unparker) -> TimerKey {
+        // A deadline past what `Instant` can hold never comes, so it stays pending, off the heap,
+        // until cancelled
+        let deadline = Instant::now().checked_add(duration);
+        match deadline {
+            Some(deadline) => self.insert(deadline, unparker),
+            None => {
+                let mut state = self.state.lock().unwrap();
+                let key = state.next_key;
+                state.next_key += 1;
+                state.pending.insert(key, unparker);
+                TimerKey(key)
+            }
+        }
+    }
+
+    /// Cancels the timer behind `key`
+    ///
+    /// return `true` if it hadn't fired yet
+    pub fn cancel(&self, key: TimerKey) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let cancelled = state.pending.remove(&key.0).is_some();
+        // Every live entry of the heap is pending, so with the heap over twice the size, more
+        // than half of it is cancelled timers that are yet to surface
+        if state.heap.len() > 2 * state.pending.len() {
+            let State { heap, pending, .. } = &mut *state;
+            heap.retain(|&Reverse((_, key))| pending.contains_key(&key));
+        }
+        cancelled
+    }
+
+    /// Return the earliest deadline among the timers that haven't fired
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.state.lock().unwrap().next_deadline()
+    }
+
+    /// Notifies the unparkers of every timer whose deadline has passed
+    ///
+    /// return the number of timers fired
+    pub fn fire_due(&self) -> usize {
+        let due = self.state.lock().unwrap().take_due(Instant::now());
+        due.iter().for_each(|u| { u.unpark(); });
+        due.len()
+    }
+
+    /// Fires the timers that are due, parks `parker` until the next deadline, or until it's
+    /// unparked, and fires whatever is due by then
+    ///
+    /// Only one thread should drive the timers this way. Inserting an earlier deadline than the
+    /// one `parker` waits for unparks it.
+    ///
+    /// return the number of timers fired
+    pub fn park_until_next_timer(&self, parker: &Parker) -> usize {
+        let (due, next) = {
+            let mut state = self.state.lock().unwrap();
+            if state.driver.as_ref().map(Unparker::id) != Some(parker.id()) {
+                state.driver = Some(parker.unparker());
+            }
+            (state.take_due(Instant::now()), state.next_deadline())
+        };
+        due.iter().for_each(|u| { u.unpark(); });
+        if !due.is_empty() {
+            // The next deadline may have passed while firing
+            return due.len() + self.fire_due();
+        }
+
+        match next {
+            Some(deadline) => { parker.park_deadline(deadline); }
+            None => parker.park()
+        }
+        self.fire_due()
+    }
+
+    /// Return the number of timers that haven't fired or been cancelled
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().pending.len()
+    }
+
+    /// Return `true` if every timer has fired or been cancelled
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the number of deadlines in the heap, including cancelled timers not yet dropped
+    #[doc(hidden)]
+    pub fn heap_len(&self) -> usize {
+        self.state.lock().unwrap().heap.len()
+    }
+}
+
+impl std::fmt::Debug for Timers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad("Timers { .. }")
+    }
+}
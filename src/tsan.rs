@@ -0,0 +1,31 @@
+//! Happens-before annotations for ThreadSanitizer
+//!
+//! TSan only sees the synchronization it can instrument. The handoff between `unpark` and the
+//! park that consumes its notification can go through a futex, a kernel object or a C runtime,
+//! so without these it may report races on data published before `unpark` and read after `park`.
+//! The functions come from the TSan runtime, so the `tsan` feature only turns these on when
+//! building with `-Zsanitizer=thread`.
+
+use std::os::raw::c_void;
+
+use crate::atomic::AtomicU32;
+
+extern "C" {
+    fn __tsan_acquire(addr: *mut c_void);
+    fn __tsan_release(addr: *mut c_void);
+}
+
+/// Marks everything before this point as visible to the thread that next calls `acquire` on
+/// `word`
+#[inline]
+pub(crate) fn release(word: &AtomicU32) {
+    // SAFETY: the runtime only uses the address as a key
+    unsafe { __tsan_release(word.as_ptr().cast()) }
+}
+
+/// Pairs with earlier `release` calls on `word`
+#[inline]
+pub(crate) fn acquire(word: &AtomicU32) {
+    // SAFETY: the runtime only uses the address as a key
+    unsafe { __tsan_acquire(word.as_ptr().cast()) }
+}
@@ -0,0 +1,90 @@
+//! Waiting for an unpark on an io_uring, alongside I/O
+//!
+//! A thread that drives a ring can't also block in `Parker::park`. Instead it submits the
+//! `Entry` of a `UringWait` with the rest of its operations, and the unpark completes it like
+//! any other operation. Needs Linux 6.7 or later for `IORING_OP_FUTEX_WAIT`.
+
+use io_uring::{opcode, squeue::Entry};
+
+use crate::parker::ParkRecord;
+use crate::Parker;
+
+/// `futex2(2)` flags matching the `FUTEX_WAIT`/`FUTEX_WAKE` calls of the futex backend
+const FUTEX2_SIZE_U32: u32 = 0x02;
+const FUTEX2_PRIVATE: u32 = libc::FUTEX_PRIVATE_FLAG as u32;
+
+/// A park in progress on an io_uring, started by `Parker::uring_wait`
+///
+/// The parker stays parked until `finish` or drop. Notifications in the meantime complete the
+/// submitted `entry`, and the completion, whatever its result, is only a hint to call `finish`.
+#[must_use = "the parker stays parked until `finish` or drop"]
+pub struct UringWait<'a> {
+    parker: &'a Parker,
+    expected: u32,
+    /// Taken when the park ends
+    record: Option<ParkRecord<'a>>
+}
+
+impl Parker {
+
+    /// Starts parking for a wait submitted to an io_uring, so that one `submit_and_wait` can
+    /// block on both I/O and this parker
+    ///
+    /// return `None`, after consuming it, if a notification is already pending, the same as
+    /// `park` returning immediately
+    pub fn uring_wait(&self) -> Option<UringWait<'_>> {
+        let (expected, record) = self.inner.begin_external_wait()?;
+        Some(UringWait {
+            parker: self,
+            expected,
+            record: Some(record)
+        })
+    }
+}
+
+impl UringWait<'_> {
+
+    /// Return the `IORING_OP_FUTEX_WAIT` submission that completes once the parker is notified
+    ///
+    /// The entry refers to memory owned by the parker and its unparkers, so it must not be
+    /// pushed after all of them are dropped. It may complete spuriously, and it completes at
+    /// once if the notification came first.
+    ///
+    /// On Linux 6.16 and later, a wait submitted while the process has a single thread can miss
+    /// unparks once further threads start, as the kernel moves their futexes to a hash of their
+    /// own without the ring's waits. Start the unparking threads first.
+    pub fn entry(&self) -> Entry {
+        opcode::FutexWait::new(
+            self.parker.inner.futex().as_ptr(),
+            u64::from(self.expected),
+            libc::FUTEX_BITSET_MATCH_ANY as u32 as u64,
+            FUTEX2_SIZE_U32 | FUTEX2_PRIVATE
+        ).build()
+    }
+
+    /// Ends the park, going back into unnotified state
+    ///
+    /// return `true` if notified since `uring_wait`
+    pub fn finish(mut self) -> bool {
+        self.end()
+    }
+
+    fn end(&mut self) -> bool {
+        match self.record.take() {
+            Some(record) => self.parker.inner.end_external_wait(record),
+            None => false
+        }
+    }
+}
+
+impl Drop for UringWait<'_> {
+    fn drop(&mut self) {
+        self.end();
+    }
+}
+
+impl std::fmt::Debug for UringWait<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad("UringWait { .. }")
+    }
+}
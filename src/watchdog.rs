@@ -0,0 +1,62 @@
+use std::fmt::Formatter;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A park that has been blocked for longer than the watchdog threshold
+///
+/// Passed to the callback registered with `ParkerBuilder::watchdog`.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct Stall<'a> {
+    /// Name of the parked thread, if it has one
+    pub thread_name: Option<&'a str>,
+    /// Identifier of the parker, see `Parker::id`
+    pub parker_id: usize,
+    /// How long the thread has been parked so far
+    pub elapsed: Duration
+}
+
+pub(crate) type StallCallback = Arc<dyn Fn(&Stall<'_>) + Send + Sync>;
+
+#[derive(Clone)]
+pub(crate) struct Watchdog {
+    pub(crate) threshold: Duration,
+    pub(crate) callback: StallCallback
+}
+
+impl std::fmt::Debug for Watchdog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Watchdog").field("threshold", &self.threshold).finish_non_exhaustive()
+    }
+}
+
+/// Tracks when a single park is next due to be reported
+pub(crate) struct StallClock<'a> {
+    watchdog: &'a Watchdog,
+    parker_id: usize,
+    start: Instant,
+    next_report: Instant
+}
+
+impl<'a> StallClock<'a> {
+
+    pub(crate) fn start(watchdog: &'a Watchdog, parker_id: usize) -> StallClock<'a> {
+        let start = Instant::now();
+        StallClock { watchdog, parker_id, start, next_report: start + watchdog.threshold }
+    }
+
+    pub(crate) fn next_report(&self) -> Instant {
+        self.next_report
+    }
+
+    /// Invokes the callback and schedules the next report one threshold later
+    pub(crate) fn report(&mut self) {
+        let thread = std::thread::current();
+        (self.watchdog.callback)(&Stall {
+            thread_name: thread.name(),
+            parker_id: self.parker_id,
+            elapsed: self.start.elapsed()
+        });
+        self.next_report += self.watchdog.threshold;
+    }
+}
@@ -0,0 +1,190 @@
+//! A parker for Zephyr RTOS, blocking on a `k_sem`
+//!
+//! Like `embedded::Parker` it needs neither `std` nor atomics and can be unparked from interrupt
+//! handlers, but the parked thread sleeps in the kernel rather than in a wait hook, and timed
+//! parks are available.
+//!
+//! The semaphore is initialized on first use and must never move afterwards, so parkers live
+//! in `static`s:
+//!
+//! ```ignore
+//! static PARKER: parking::zephyr::Parker = parking::zephyr::Parker::new();
+//!
+//! PARKER.park_timeout(Duration::from_millis(10));
+//! ```
+//!
+//! The kernel is called through the `z_impl_*` functions, so parking threads must run in
+//! supervisor mode. Zephyr must be built with `CONFIG_TIMEOUT_64BIT` (the default), and if its
+//! `CONFIG_SYS_CLOCK_TICKS_PER_SEC` isn't the default of 10000, the same value must be given in
+//! the `PARKING_ZEPHYR_TICKS_PER_SEC` environment variable at build time.
+
+use core::cell::{Cell, UnsafeCell};
+use core::fmt::Formatter;
+use core::time::Duration;
+
+use critical_section::Mutex;
+
+use crate::config::parse_rate;
+
+const TICKS_PER_SEC: u64 = match option_env!("PARKING_ZEPHYR_TICKS_PER_SEC") {
+    Some(rate) => parse_rate(rate),
+    None => 10_000
+};
+
+/// `k_timeout_t` with `CONFIG_TIMEOUT_64BIT`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Timeout {
+    ticks: i64
+}
+
+const K_FOREVER: Timeout = Timeout { ticks: -1 };
+
+/// Storage for a `struct k_sem`, whose layout depends on the kernel configuration. Room for the
+/// largest one, with a scalable wait queue, poll events, object tracking and object core.
+#[repr(C, align(8))]
+struct Sem(UnsafeCell<[u8; 128]>);
+
+extern "C" {
+    fn z_impl_k_sem_init(sem: *mut Sem, initial_count: u32, limit: u32) -> i32;
+    fn z_impl_k_sem_take(sem: *mut Sem, timeout: Timeout) -> i32;
+    fn z_impl_k_sem_give(sem: *mut Sem);
+    fn z_impl_k_uptime_ticks() -> i64;
+}
+
+/// Waits for a notification, sleeping on a kernel semaphore
+///
+/// Meant to be parked on by a single thread at a time.
+pub struct Parker {
+    /// Whether a notification is pending, and whether `sem` was initialized
+    state: Mutex<Cell<(bool, bool)>>,
+    sem: Sem
+}
+
+// SAFETY: `sem` is only touched through the kernel, which does its own locking, once the
+// critical section in `sem()` initialized it
+unsafe impl Sync for Parker {}
+
+impl Parker {
+
+    pub const fn new() -> Parker {
+        Parker {
+            state: Mutex::new(Cell::new((false, false))),
+            sem: Sem(UnsafeCell::new([0; 128]))
+        }
+    }
+
+    /// Blocks until notified and then goes back into unnotified state
+    pub fn park(&'static self) {
+        while !self.try_park() {
+            // SAFETY: initialized and never moved, see `sem()`
+            unsafe { z_impl_k_sem_take(self.sem(), K_FOREVER) };
+        }
+    }
+
+    /// Blocks until notified and then goes back into unnotified state, or times out after `duration`
+    ///
+    /// return `true` if notified before the timeout
+    pub fn park_timeout(&'static self, duration: Duration) -> bool {
+        // SAFETY: plain reads of the kernel's tick counter
+        let deadline = unsafe { z_impl_k_uptime_ticks() }.saturating_add(to_ticks(duration));
+        loop {
+            if self.try_park() {
+                return true;
+            }
+            let remaining = deadline - unsafe { z_impl_k_uptime_ticks() };
+            if remaining <= 0 {
+                return false;
+            }
+            // SAFETY: initialized and never moved, see `sem()`
+            unsafe { z_impl_k_sem_take(self.sem(), Timeout { ticks: remaining }) };
+        }
+    }
+
+    /// Consumes a notification without blocking
+    ///
+    /// return `true` if the parker was notified
+    pub fn try_park(&self) -> bool {
+        critical_section::with(|cs| {
+            let state = self.state.borrow(cs);
+            let (notified, initialized) = state.get();
+            state.set((false, initialized));
+            notified
+        })
+    }
+
+    /// Notifies the parker
+    ///
+    /// return `true` if this call is the first to notify the parker, or `false`
+    /// if the parker was already notified
+    pub fn unpark(&'static self) -> bool {
+        let first = critical_section::with(|cs| {
+            let state = self.state.borrow(cs);
+            let (notified, initialized) = state.get();
+            state.set((true, initialized));
+            !notified
+        });
+        // A binary semaphore, so a give without a taker is remembered once and then ignored
+        // SAFETY: initialized and never moved, see `sem()`
+        unsafe { z_impl_k_sem_give(self.sem()) };
+        first
+    }
+
+    /// Return a handle for unparking
+    pub fn unparker(&'static self) -> Unparker {
+        Unparker { parker: self }
+    }
+
+    /// Return the semaphore, initializing it the first time. `'static` guarantees it never moves
+    /// once the kernel holds pointers into it.
+    fn sem(&'static self) -> *mut Sem {
+        let sem = &self.sem as *const Sem as *mut Sem;
+        critical_section::with(|cs| {
+            let state = self.state.borrow(cs);
+            let (notified, initialized) = state.get();
+            if !initialized {
+                // SAFETY: nobody else can use `sem` before `initialized` is set
+                unsafe { z_impl_k_sem_init(sem, 0, 1) };
+                state.set((notified, true));
+            }
+        });
+        sem
+    }
+}
+
+impl Default for Parker {
+    fn default() -> Self {
+        Parker::new()
+    }
+}
+
+impl core::fmt::Debug for Parker {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.pad("Parker { .. }")
+    }
+}
+
+/// Notifies a parker
+#[derive(Clone, Copy)]
+pub struct Unparker {
+    parker: &'static Parker
+}
+
+impl Unparker {
+    /// Notifies the parker, see `Parker::unpark`
+    pub fn unpark(&self) -> bool {
+        self.parker.unpark()
+    }
+}
+
+impl core::fmt::Debug for Unparker {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.pad("Unparker { .. }")
+    }
+}
+
+/// Rounds up so a timed park never returns before its timeout
+fn to_ticks(duration: Duration) -> i64 {
+    let ticks = (duration.as_nanos() * TICKS_PER_SEC as u128).div_ceil(1_000_000_000);
+    if ticks > i64::MAX as u128 { i64::MAX } else { ticks as i64 }
+}
@@ -0,0 +1,16 @@
+//! Constants shared by the suites
+//!
+//! Every suite but tests/custom_backend.rs is compiled out with `custom-backend`: a custom
+//! backend has to be registered by the application, so there is nothing for them to run there.
+//! Those that need threads are compiled out on single-threaded targets too.
+
+// Each suite uses only some of these
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+/// How long a test lets pass when it needs the other side to be blocked, or a timeout to run out
+pub const TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Upper bound on any wakeup, loose as a loaded machine can hold one up
+pub const SLACK: Duration = Duration::from_secs(5);
@@ -0,0 +1,114 @@
+//! Parks through a `Backend` registered with `set_backend`, which the `custom-backend` feature
+//! makes every parker block on.
+//!
+//! The other suites can't run with the feature on, as nothing registers a backend for them.
+//! This one registers a futex emulated on a `Mutex` and `Condvar`, and checks that parks block
+//! in it, that unparks wake them and that timeouts hold.
+
+#![cfg(all(feature = "std", parking_futex = "custom"))]
+
+mod common;
+
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::atomic::{AtomicU32, AtomicUsize};
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parking::{Backend, Parker};
+
+use common::{SLACK, TIMEOUT};
+
+/// One lock and condition variable for every futex, so a wake may wake waiters on other words,
+/// which the interface allows as spurious wakeups
+struct TestBackend {
+    lock: Mutex<()>,
+    cvar: Condvar,
+    /// Number of calls to `wait`
+    waits: AtomicUsize
+}
+
+impl Backend for TestBackend {
+    fn wait(&self, futex: &AtomicU32, expected: u32, timeout: Option<Duration>) {
+        self.waits.fetch_add(1, SeqCst);
+        let guard = self.lock.lock().unwrap();
+        // Checked under the lock, which `wake_one` takes too, so a wake can't slip in between
+        if futex.load(SeqCst) != expected {
+            return;
+        }
+        match timeout {
+            None => drop(self.cvar.wait(guard).unwrap()),
+            Some(timeout) => drop(self.cvar.wait_timeout(guard, timeout).unwrap())
+        }
+    }
+
+    fn wake_one(&self, _futex: &AtomicU32) {
+        drop(self.lock.lock().unwrap());
+        self.cvar.notify_all();
+    }
+}
+
+static BACKEND: TestBackend = TestBackend {
+    lock: Mutex::new(()),
+    cvar: Condvar::new(),
+    waits: AtomicUsize::new(0)
+};
+
+/// Registers the backend, for whichever test runs first
+fn register() {
+    let _ = parking::set_backend(&BACKEND);
+}
+
+#[test]
+fn unpark_wakes_a_park_blocked_in_the_backend() {
+    register();
+    let parker = Parker::new();
+    let unparker = parker.unparker();
+    let waits = BACKEND.waits.load(SeqCst);
+    let t = thread::spawn(move || {
+        thread::sleep(TIMEOUT);
+        unparker.unpark();
+    });
+    parker.park();
+    t.join().unwrap();
+    assert!(BACKEND.waits.load(SeqCst) > waits);
+}
+
+#[test]
+fn pending_notification_returns_without_blocking() {
+    register();
+    let parker = Parker::new();
+    assert!(parker.unpark());
+    assert!(!parker.unpark());
+    let start = Instant::now();
+    parker.park();
+    assert!(start.elapsed() < SLACK);
+}
+
+#[test]
+fn times_out_no_earlier_than_the_deadline() {
+    register();
+    let parker = Parker::new();
+    for _ in 0..3 {
+        let start = Instant::now();
+        assert!(!parker.park_timeout(TIMEOUT));
+        let elapsed = start.elapsed();
+        assert!(elapsed >= TIMEOUT, "timed out after {:?}", elapsed);
+        assert!(elapsed < TIMEOUT + SLACK, "timed out after {:?}", elapsed);
+    }
+}
+
+#[test]
+fn unpark_ends_a_timed_park_early() {
+    register();
+    let parker = Parker::new();
+    let unparker = parker.unparker();
+    let start = Instant::now();
+    let t = thread::spawn(move || {
+        thread::sleep(TIMEOUT);
+        unparker.unpark();
+    });
+    assert!(parker.park_timeout(Duration::from_secs(60)));
+    assert!(start.elapsed() < SLACK);
+    t.join().unwrap();
+}
@@ -0,0 +1,93 @@
+//! `FairMutex` across threads: the lock goes to waiters in the order they queued, no thread
+//! barges in on a handoff, and a timed lock that gives up leaves the queue.
+//!
+//! Threads are queued one at a time, each given time to park before the next comes, as nothing
+//! outside the mutex can tell when a thread has queued.
+
+#![cfg(all(feature = "std", not(parking_futex = "custom"), not(parking_single_threaded)))]
+
+mod common;
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+use parking::FairMutex;
+
+use common::TIMEOUT;
+
+#[test]
+fn lock_is_handed_to_waiters_in_queue_order() {
+    let mutex = Arc::new(FairMutex::new(Vec::new()));
+    let guard = mutex.lock();
+    let waiters: Vec<_> = (0..5).map(|id| {
+        let mutex = mutex.clone();
+        let t = thread::spawn(move || mutex.lock().push(id));
+        thread::sleep(TIMEOUT);
+        t
+    }).collect();
+
+    drop(guard);
+    for t in waiters {
+        t.join().unwrap();
+    }
+    assert_eq!(*mutex.lock(), [0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn unlock_hands_off_without_letting_another_thread_in() {
+    let mutex = Arc::new(FairMutex::new(()));
+    let guard = mutex.lock();
+    let waiter = {
+        let mutex = mutex.clone();
+        thread::spawn(move || {
+            let _guard = mutex.lock();
+            thread::sleep(TIMEOUT);
+        })
+    };
+    thread::sleep(TIMEOUT);
+
+    drop(guard);
+    // The waiter owns the lock from the unlock on, whether or not it has woken up yet
+    assert!(mutex.try_lock().is_none());
+    waiter.join().unwrap();
+    assert!(mutex.try_lock().is_some());
+}
+
+#[test]
+fn lock_timeout_gives_up_and_leaves_the_queue() {
+    let mutex = Arc::new(FairMutex::new(()));
+    let guard = mutex.lock();
+    let waiter = {
+        let mutex = mutex.clone();
+        thread::spawn(move || {
+            let start = Instant::now();
+            let timed_out = mutex.lock_timeout(TIMEOUT).is_none();
+            (timed_out, start.elapsed())
+        })
+    };
+    let (timed_out, elapsed) = waiter.join().unwrap();
+    assert!(timed_out);
+    assert!(elapsed >= TIMEOUT);
+
+    // With the waiter gone, unlocking leaves the mutex unlocked
+    drop(guard);
+    assert!(mutex.try_lock().is_some());
+}
+
+#[test]
+fn counts_survive_contention() {
+    let mutex = Arc::new(FairMutex::new(0));
+    let threads: Vec<_> = (0..4).map(|_| {
+        let mutex = mutex.clone();
+        thread::spawn(move || {
+            for _ in 0..1000 {
+                *mutex.lock() += 1;
+            }
+        })
+    }).collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(*mutex.lock(), 4000);
+}
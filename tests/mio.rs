@@ -0,0 +1,82 @@
+//! `Unparker::with_mio_waker`: the handle it returns reaches the parker the way the wrapped
+//! unparker does, and wakes the `mio::Poll` only along with it.
+
+#![cfg(all(feature = "mio", not(parking_futex = "custom"), not(parking_single_threaded)))]
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use mio::{Events, Poll, Token, Waker};
+use parking::Parker;
+
+const WAKE: Token = Token(0);
+
+/// Return whether the waker of `poll` was woken, without blocking
+fn poll_woken(poll: &mut Poll) -> bool {
+    let mut events = Events::with_capacity(8);
+    poll.poll(&mut events, Some(Duration::ZERO)).unwrap();
+    events.iter().any(|event| event.token() == WAKE)
+}
+
+#[test]
+fn unpark_notifies_the_parker_and_wakes_the_poll() {
+    let mut poll = Poll::new().unwrap();
+    let waker = Arc::new(Waker::new(poll.registry(), WAKE).unwrap());
+    let parker = Parker::new();
+    let unparker = parker.unparker().with_mio_waker(waker);
+    assert!(unparker.unpark());
+    assert!(!unparker.unpark());
+    assert!(poll_woken(&mut poll));
+    assert!(parker.park_timeout(Duration::ZERO));
+}
+
+#[test]
+fn unpark_now_leaves_an_idle_parker_and_the_poll_alone() {
+    let mut poll = Poll::new().unwrap();
+    let waker = Arc::new(Waker::new(poll.registry(), WAKE).unwrap());
+    let parker = Parker::new();
+    let unparker = parker.unparker().with_mio_waker(waker);
+    assert!(!unparker.unpark_now());
+    assert!(!parker.park_timeout(Duration::ZERO));
+    assert!(!poll_woken(&mut poll));
+}
+
+#[test]
+fn unpark_flags_sets_the_flags_on_the_parker_and_wakes_the_poll() {
+    let mut poll = Poll::new().unwrap();
+    let waker = Arc::new(Waker::new(poll.registry(), WAKE).unwrap());
+    let parker = Parker::new();
+    let unparker = parker.unparker().with_mio_waker(waker);
+    assert!(unparker.unpark_flags(0b01));
+    assert!(!unparker.unpark_flags(0b10));
+    assert!(poll_woken(&mut poll));
+    assert_eq!(parker.park_flags_timeout(Duration::ZERO), 0b11);
+}
+
+#[test]
+fn unpark_if_gen_checks_the_generation_of_the_parker() {
+    let mut poll = Poll::new().unwrap();
+    let waker = Arc::new(Waker::new(poll.registry(), WAKE).unwrap());
+    let parker = Parker::new();
+    let unparker = parker.unparker().with_mio_waker(waker);
+    let stale = parker.generation().wrapping_sub(1);
+    assert!(!unparker.unpark_if_gen(stale));
+    assert!(!poll_woken(&mut poll));
+    assert!(!parker.park_timeout(Duration::ZERO));
+
+    assert!(unparker.unpark_if_gen(parker.generation()));
+    assert!(poll_woken(&mut poll));
+    assert!(parker.park_timeout(Duration::ZERO));
+}
+
+#[test]
+fn debug_state_is_that_of_the_parker() {
+    let poll = Poll::new().unwrap();
+    let waker = Arc::new(Waker::new(poll.registry(), WAKE).unwrap());
+    let parker = Parker::new();
+    let unparker = parker.unparker().with_mio_waker(waker);
+    unparker.unpark();
+    let state = unparker.debug_state().unwrap();
+    assert_eq!(state.parker_id, parker.id());
+    assert!(state.notified);
+}
@@ -0,0 +1,106 @@
+//! `Monitor` across threads: `wait` releases the lock and parks until a notification, waiters
+//! are woken in the order they started waiting, and a notification wakes only threads already
+//! waiting.
+//!
+//! A waiter is queued before `wait` releases the lock, so whoever takes the lock after a waiter
+//! wrote to the data knows it's queued.
+
+#![cfg(all(feature = "std", not(parking_futex = "custom"), not(parking_single_threaded)))]
+
+mod common;
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+use parking::Monitor;
+
+use common::TIMEOUT;
+
+/// Yields until `condition` holds for the data of `monitor`, checking it under the lock
+fn poll_until<T>(monitor: &Monitor<T>, mut condition: impl FnMut(&T) -> bool) {
+    while !condition(&monitor.lock()) {
+        thread::yield_now();
+    }
+}
+
+#[test]
+fn wait_while_parks_until_notified_of_the_change() {
+    let monitor = Arc::new(Monitor::new(None));
+    let consumer = {
+        let monitor = monitor.clone();
+        thread::spawn(move || monitor.lock().wait_while(|value| value.is_none()).take())
+    };
+    thread::sleep(TIMEOUT);
+    *monitor.lock() = Some(7);
+    monitor.notify_all();
+    assert_eq!(consumer.join().unwrap(), Some(7));
+}
+
+#[test]
+fn notify_one_wakes_waiters_in_the_order_they_started_waiting() {
+    // How many are waiting, and the order they were woken in
+    let monitor = Arc::new(Monitor::new((0, Vec::new())));
+    let waiters: Vec<_> = (0..5).map(|id| {
+        let t = {
+            let monitor = monitor.clone();
+            thread::spawn(move || {
+                let mut guard = monitor.lock();
+                guard.0 += 1;
+                let mut guard = guard.wait();
+                guard.1.push(id);
+            })
+        };
+        poll_until(&monitor, |state| state.0 == id + 1);
+        t
+    }).collect();
+
+    for woken in 1..=5 {
+        assert!(monitor.notify_one());
+        poll_until(&monitor, |state| state.1.len() == woken);
+    }
+    assert!(!monitor.notify_one());
+    for t in waiters {
+        t.join().unwrap();
+    }
+    assert_eq!(monitor.lock().1, [0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn notify_all_wakes_every_waiter() {
+    let monitor = Arc::new(Monitor::new(0));
+    let waiters: Vec<_> = (0..4).map(|_| {
+        let monitor = monitor.clone();
+        thread::spawn(move || {
+            let mut guard = monitor.lock();
+            *guard += 1;
+            drop(guard.wait());
+        })
+    }).collect();
+    poll_until(&monitor, |waiting| *waiting == 4);
+
+    assert_eq!(monitor.notify_all(), 4);
+    for t in waiters {
+        t.join().unwrap();
+    }
+    assert_eq!(monitor.notify_all(), 0);
+}
+
+#[test]
+fn wait_timeout_times_out_without_a_notification() {
+    let monitor = Monitor::new(());
+    let start = Instant::now();
+    let (_guard, notified) = monitor.lock().wait_timeout(TIMEOUT);
+    assert!(!notified);
+    assert!(start.elapsed() >= TIMEOUT);
+    // The waiter that timed out left the queue
+    assert!(!monitor.notify_one());
+}
+
+#[test]
+fn notification_without_waiters_is_lost() {
+    let monitor = Monitor::new(());
+    assert!(!monitor.notify_one());
+    let (_guard, notified) = monitor.lock().wait_timeout(TIMEOUT);
+    assert!(!notified);
+}
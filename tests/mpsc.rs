@@ -0,0 +1,119 @@
+//! Channels from `parking::mpsc` across threads: messages arrive in order, the receiver parks
+//! until there is one, a full `sync_channel` parks its sender, and either end going away
+//! disconnects the other the way std's channels do.
+
+#![cfg(all(feature = "std", not(parking_futex = "custom"), not(parking_single_threaded)))]
+
+mod common;
+
+use std::thread;
+use std::time::Instant;
+
+use parking::mpsc::{self, RecvError, RecvTimeoutError, TryRecvError, TrySendError};
+
+use common::TIMEOUT;
+
+#[test]
+fn messages_from_many_senders_all_arrive_in_order() {
+    let (tx, rx) = mpsc::channel();
+    let senders: Vec<_> = (0..4).map(|id| {
+        let tx = tx.clone();
+        thread::spawn(move || {
+            for i in 0..1000 {
+                tx.send((id, i)).unwrap();
+            }
+        })
+    }).collect();
+    drop(tx);
+
+    let mut next = [0; 4];
+    for (id, i) in rx.iter() {
+        assert_eq!(i, next[id], "sender {} out of order", id);
+        next[id] += 1;
+    }
+    assert_eq!(next, [1000; 4]);
+    for sender in senders {
+        sender.join().unwrap();
+    }
+}
+
+#[test]
+fn recv_parks_until_a_message_is_sent() {
+    let (tx, rx) = mpsc::channel();
+    let t = thread::spawn(move || {
+        thread::sleep(TIMEOUT);
+        tx.send(7).unwrap();
+    });
+    let start = Instant::now();
+    assert_eq!(rx.recv(), Ok(7));
+    assert!(start.elapsed() >= TIMEOUT);
+    t.join().unwrap();
+}
+
+#[test]
+fn recv_timeout_times_out_without_a_message() {
+    let (_tx, rx) = mpsc::channel::<()>();
+    let start = Instant::now();
+    assert_eq!(rx.recv_timeout(TIMEOUT), Err(RecvTimeoutError::Timeout));
+    assert!(start.elapsed() >= TIMEOUT);
+}
+
+#[test]
+fn dropping_every_sender_disconnects_after_draining() {
+    let (tx, rx) = mpsc::channel();
+    tx.send(1).unwrap();
+    let tx2 = tx.clone();
+    drop(tx);
+    tx2.send(2).unwrap();
+    drop(tx2);
+    assert_eq!(rx.recv(), Ok(1));
+    assert_eq!(rx.recv(), Ok(2));
+    assert_eq!(rx.recv(), Err(RecvError));
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn dropping_the_last_sender_wakes_a_parked_receiver() {
+    let (tx, rx) = mpsc::channel::<()>();
+    let t = thread::spawn(move || {
+        thread::sleep(TIMEOUT);
+        drop(tx);
+    });
+    assert_eq!(rx.recv(), Err(RecvError));
+    t.join().unwrap();
+}
+
+#[test]
+fn dropping_the_receiver_fails_sends() {
+    let (tx, rx) = mpsc::sync_channel(1);
+    drop(rx);
+    assert_eq!(tx.send(1).unwrap_err().0, 1);
+    assert_eq!(tx.try_send(2), Err(TrySendError::Disconnected(2)));
+}
+
+#[test]
+fn full_sync_channel_parks_the_sender_until_a_recv() {
+    let (tx, rx) = mpsc::sync_channel(1);
+    tx.send(1).unwrap();
+    assert_eq!(tx.try_send(2), Err(TrySendError::Full(2)));
+
+    let t = thread::spawn(move || {
+        let start = Instant::now();
+        tx.send(2).unwrap();
+        start.elapsed()
+    });
+    thread::sleep(TIMEOUT);
+    assert_eq!(rx.recv(), Ok(1));
+    assert!(t.join().unwrap() >= TIMEOUT);
+    assert_eq!(rx.recv(), Ok(2));
+}
+
+#[test]
+fn dropping_the_receiver_wakes_a_parked_sender() {
+    let (tx, rx) = mpsc::sync_channel(1);
+    tx.send(1).unwrap();
+    let t = thread::spawn(move || tx.send(2));
+    thread::sleep(TIMEOUT);
+    drop(rx);
+    assert_eq!(t.join().unwrap().unwrap_err().0, 2);
+}
@@ -0,0 +1,74 @@
+//! Channels from `parking::oneshot` across threads: a receiver parked before the send gets the
+//! value, one that comes later finds it waiting, and dropping either end without sending or
+//! receiving disconnects the other.
+
+#![cfg(all(feature = "std", not(parking_futex = "custom"), not(parking_single_threaded)))]
+
+mod common;
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+use parking::mpsc::{RecvError, RecvTimeoutError, TryRecvError};
+
+use common::TIMEOUT;
+
+#[test]
+fn receiver_parked_before_the_send_gets_the_value() {
+    let (tx, rx) = parking::oneshot();
+    let t = thread::spawn(move || {
+        let start = Instant::now();
+        (rx.recv(), start.elapsed())
+    });
+    // Long enough for the receiver to be parked when the value comes
+    thread::sleep(TIMEOUT);
+    tx.send(7).unwrap();
+    let (value, elapsed) = t.join().unwrap();
+    assert_eq!(value, Ok(7));
+    assert!(elapsed >= TIMEOUT);
+}
+
+#[test]
+fn value_sent_before_the_receive_waits_for_it() {
+    let (tx, rx) = parking::oneshot();
+    thread::spawn(move || tx.send(7).unwrap()).join().unwrap();
+    assert_eq!(rx.recv(), Ok(7));
+    assert_eq!(rx.recv(), Err(RecvError));
+}
+
+#[test]
+fn recv_timeout_times_out_without_a_send() {
+    let (_tx, rx) = parking::oneshot::<()>();
+    let start = Instant::now();
+    assert_eq!(rx.recv_timeout(TIMEOUT), Err(RecvTimeoutError::Timeout));
+    assert!(start.elapsed() >= TIMEOUT);
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn dropping_the_sender_wakes_a_parked_receiver() {
+    let (tx, rx) = parking::oneshot::<()>();
+    let t = thread::spawn(move || rx.recv());
+    thread::sleep(TIMEOUT);
+    drop(tx);
+    assert_eq!(t.join().unwrap(), Err(RecvError));
+}
+
+#[test]
+fn dropping_the_receiver_fails_the_send() {
+    let (tx, rx) = parking::oneshot();
+    assert!(!tx.is_closed());
+    drop(rx);
+    assert!(tx.is_closed());
+    assert_eq!(tx.send(7).unwrap_err().0, 7);
+}
+
+#[test]
+fn value_never_received_is_dropped_with_the_receiver() {
+    let value = Arc::new(());
+    let (tx, rx) = parking::oneshot();
+    tx.send(value.clone()).unwrap();
+    drop(rx);
+    assert_eq!(Arc::strong_count(&value), 1);
+}
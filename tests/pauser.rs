@@ -0,0 +1,112 @@
+//! `Pauser` with real workers: after `pause` every worker parks at its next checkpoint and makes
+//! no progress until `resume`, `wait_paused` returns once they all have, and the cycle repeats.
+
+#![cfg(all(feature = "std", not(parking_futex = "custom"), not(parking_single_threaded)))]
+
+mod common;
+
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use parking::Pauser;
+
+use common::TIMEOUT;
+
+const WORKERS: usize = 4;
+
+struct Pool {
+    pauser: Arc<Pauser>,
+    /// Iterations each worker made
+    progress: Arc<Vec<AtomicUsize>>,
+    shutdown: Arc<AtomicBool>,
+    workers: Vec<JoinHandle<()>>
+}
+
+impl Pool {
+    fn start() -> Pool {
+        let pauser = Arc::new(Pauser::new());
+        let progress = Arc::new((0..WORKERS).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>());
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let workers = (0..WORKERS).map(|i| {
+            let (pauser, progress, shutdown) = (pauser.clone(), progress.clone(), shutdown.clone());
+            thread::spawn(move || {
+                while !shutdown.load(SeqCst) {
+                    progress[i].fetch_add(1, SeqCst);
+                    pauser.checkpoint();
+                }
+            })
+        }).collect();
+        Pool { pauser, progress, shutdown, workers }
+    }
+
+    fn progress(&self) -> Vec<usize> {
+        self.progress.iter().map(|p| p.load(SeqCst)).collect()
+    }
+
+    fn stop(self) {
+        self.shutdown.store(true, SeqCst);
+        self.pauser.resume();
+        for worker in self.workers {
+            worker.join().unwrap();
+        }
+    }
+}
+
+#[test]
+fn paused_workers_are_parked_at_a_checkpoint_until_resume() {
+    let pool = Pool::start();
+    pool.pauser.pause();
+    assert!(pool.pauser.is_paused());
+    pool.pauser.wait_paused(WORKERS);
+    assert_eq!(pool.pauser.paused(), WORKERS);
+
+    // Parked workers make no progress
+    let before = pool.progress();
+    thread::sleep(TIMEOUT);
+    assert_eq!(pool.progress(), before);
+
+    pool.pauser.resume();
+    assert!(!pool.pauser.is_paused());
+    let start = Instant::now();
+    while pool.progress().iter().zip(&before).any(|(now, then)| now == then) {
+        assert!(start.elapsed() < Duration::from_secs(5), "workers didn't resume");
+        thread::yield_now();
+    }
+    assert_eq!(pool.pauser.paused(), 0);
+    pool.stop();
+}
+
+#[test]
+fn pause_and_resume_repeat() {
+    let pool = Pool::start();
+    for _ in 0..20 {
+        pool.pauser.pause();
+        pool.pauser.wait_paused(WORKERS);
+        assert_eq!(pool.pauser.paused(), WORKERS);
+        pool.pauser.resume();
+    }
+    pool.stop();
+}
+
+#[test]
+fn wait_paused_timeout_gives_up_on_missing_workers() {
+    let pool = Pool::start();
+    pool.pauser.pause();
+    let start = Instant::now();
+    assert!(!pool.pauser.wait_paused_timeout(WORKERS + 1, TIMEOUT));
+    assert!(start.elapsed() >= TIMEOUT);
+    assert!(pool.pauser.wait_paused_timeout(WORKERS, Duration::from_secs(60)));
+    pool.stop();
+}
+
+#[test]
+fn checkpoint_passes_while_not_paused() {
+    let pauser = Pauser::new();
+    assert!(!pauser.checkpoint());
+    pauser.pause();
+    pauser.resume();
+    assert!(!pauser.checkpoint());
+}
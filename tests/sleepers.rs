@@ -0,0 +1,153 @@
+//! `Sleepers` with real workers: the worker loop from its documentation runs every job pushed
+//! to a pool, a notification wakes exactly one idle worker, and one that finds none is kept for
+//! the next `go_idle`.
+
+#![cfg(all(feature = "std", not(parking_futex = "custom"), not(parking_single_threaded)))]
+
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use parking::{Parker, Sleepers};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct Pool {
+    queue: Mutex<VecDeque<Job>>,
+    sleepers: Sleepers,
+    shutdown: AtomicBool
+}
+
+impl Pool {
+    fn push(&self, job: Job) {
+        self.queue.lock().unwrap().push_back(job);
+        self.sleepers.notify_one_idle();
+    }
+
+    fn pop(&self) -> Option<Job> {
+        self.queue.lock().unwrap().pop_front()
+    }
+
+    fn work(&self) {
+        let parker = Parker::new();
+        let unparker = parker.unparker();
+        while !self.shutdown.load(SeqCst) {
+            if let Some(job) = self.pop() {
+                job();
+                continue;
+            }
+            if !self.sleepers.go_idle(&unparker) {
+                continue;
+            }
+            if let Some(job) = self.pop() {
+                self.sleepers.leave_idle(&unparker);
+                job();
+                continue;
+            }
+            if self.shutdown.load(SeqCst) {
+                self.sleepers.leave_idle(&unparker);
+                break;
+            }
+            parker.park();
+            self.sleepers.leave_idle(&unparker);
+        }
+    }
+}
+
+/// Yields until `count` workers are idle
+fn wait_idle(sleepers: &Sleepers, count: usize) {
+    while sleepers.idle() < count {
+        thread::yield_now();
+    }
+}
+
+#[test]
+fn workers_run_every_job_pushed() {
+    let pool = Arc::new(Pool::default());
+    let workers: Vec<_> = (0..4).map(|_| {
+        let pool = pool.clone();
+        thread::spawn(move || pool.work())
+    }).collect();
+
+    let done = Arc::new(AtomicUsize::new(0));
+    for _ in 0..10_000 {
+        let done = done.clone();
+        pool.push(Box::new(move || { done.fetch_add(1, SeqCst); }));
+    }
+    while done.load(SeqCst) < 10_000 {
+        thread::yield_now();
+    }
+
+    pool.shutdown.store(true, SeqCst);
+    pool.sleepers.notify_all_idle();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    assert_eq!(done.load(SeqCst), 10_000);
+}
+
+#[test]
+fn notify_one_idle_wakes_exactly_one_worker() {
+    let sleepers = Arc::new(Sleepers::new());
+    let woken = Arc::new(AtomicUsize::new(0));
+    let workers: Vec<_> = (0..3).map(|_| {
+        let sleepers = sleepers.clone();
+        let woken = woken.clone();
+        thread::spawn(move || {
+            let parker = Parker::new();
+            let unparker = parker.unparker();
+            assert!(sleepers.go_idle(&unparker));
+            parker.park();
+            woken.fetch_add(1, SeqCst);
+            sleepers.leave_idle(&unparker);
+        })
+    }).collect();
+    wait_idle(&sleepers, 3);
+
+    assert!(sleepers.notify_one_idle());
+    assert_eq!(sleepers.idle(), 2);
+    while woken.load(SeqCst) < 1 {
+        thread::yield_now();
+    }
+    assert_eq!(sleepers.idle(), 2);
+    assert_eq!(woken.load(SeqCst), 1);
+
+    assert_eq!(sleepers.notify_all_idle(), 2);
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    assert_eq!(woken.load(SeqCst), 3);
+}
+
+#[test]
+fn notification_without_idle_workers_is_kept_for_the_next_go_idle() {
+    let sleepers = Sleepers::new();
+    let unparker = Parker::new().unparker();
+    assert!(!sleepers.notify_one_idle());
+    assert!(sleepers.is_pending());
+
+    assert!(!sleepers.go_idle(&unparker));
+    assert!(!sleepers.is_pending());
+    assert_eq!(sleepers.idle(), 0);
+    assert!(sleepers.go_idle(&unparker));
+    assert_eq!(sleepers.idle(), 1);
+}
+
+#[test]
+fn leave_idle_reports_a_worker_picked_by_a_notification() {
+    let sleepers = Sleepers::new();
+    let parker = Parker::new();
+    let unparker = parker.unparker();
+    assert!(sleepers.go_idle(&unparker));
+    assert!(sleepers.leave_idle(&unparker));
+
+    assert!(sleepers.go_idle(&unparker));
+    assert!(sleepers.notify_one_idle());
+    assert!(!sleepers.leave_idle(&unparker));
+    // The notification waits on the parker
+    assert!(parker.park_timeout(Duration::from_millis(0)));
+}
@@ -0,0 +1,106 @@
+//! `AtomicUnparker` across threads: a waiter following the register, check, park loop from its
+//! documentation never misses a wake, and the slot hands out whichever unparker was registered
+//! last.
+
+#![cfg(all(feature = "std", not(parking_futex = "custom"), not(parking_single_threaded)))]
+
+mod common;
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use parking::{AtomicUnparker, Parker};
+
+use common::TIMEOUT;
+
+#[test]
+fn waiter_parked_on_the_slot_is_woken() {
+    let slot = Arc::new(AtomicUnparker::new());
+    let ready = Arc::new(AtomicUsize::new(0));
+    let waker = {
+        let (slot, ready) = (slot.clone(), ready.clone());
+        thread::spawn(move || {
+            thread::sleep(TIMEOUT);
+            ready.store(1, SeqCst);
+            slot.wake();
+        })
+    };
+
+    let parker = Parker::new();
+    loop {
+        slot.register(&parker.unparker());
+        if ready.load(SeqCst) == 1 {
+            break;
+        }
+        parker.park();
+    }
+    waker.join().unwrap();
+}
+
+#[test]
+fn no_wake_is_lost_across_many_rounds() {
+    const ROUNDS: usize = 10_000;
+    let slot = Arc::new(AtomicUnparker::new());
+    // The last round the waker made ready
+    let ready = Arc::new(AtomicUsize::new(0));
+    // The last round the waiter saw
+    let seen = Arc::new(AtomicUsize::new(0));
+    let waker = {
+        let (slot, ready, seen) = (slot.clone(), ready.clone(), seen.clone());
+        thread::spawn(move || {
+            for round in 1..=ROUNDS {
+                ready.store(round, SeqCst);
+                slot.wake();
+                while seen.load(SeqCst) < round {
+                    thread::yield_now();
+                }
+            }
+        })
+    };
+
+    let parker = Parker::new();
+    let unparker = parker.unparker();
+    for round in 1..=ROUNDS {
+        loop {
+            slot.register(&unparker);
+            if ready.load(SeqCst) >= round {
+                break;
+            }
+            parker.park();
+        }
+        seen.store(round, SeqCst);
+    }
+    waker.join().unwrap();
+}
+
+#[test]
+fn wake_without_a_registered_unparker_does_nothing() {
+    let slot = AtomicUnparker::new();
+    assert!(!slot.wake());
+    assert!(slot.take().is_none());
+}
+
+#[test]
+fn take_returns_the_unparker_registered_last() {
+    let slot = AtomicUnparker::new();
+    let (first, second) = (Parker::new(), Parker::new());
+    slot.register(&first.unparker());
+    slot.register(&second.unparker());
+    assert_eq!(slot.take().map(|u| u.id()), Some(second.id()));
+    assert!(slot.take().is_none());
+}
+
+#[test]
+fn wake_unparks_the_registered_parker_once() {
+    let slot = AtomicUnparker::new();
+    let parker = Parker::new();
+    slot.register(&parker.unparker());
+    assert_eq!(parker.handle_count(), 1);
+    assert!(slot.wake());
+    assert!(!slot.wake());
+    assert_eq!(parker.handle_count(), 0);
+    assert!(parker.park_timeout(Duration::from_millis(0)));
+}
@@ -0,0 +1,112 @@
+//! Rings from `parking::spsc` across threads: elements arrive in order through a ring much
+//! smaller than the stream, each end parks while it has to wait, and dropping either end
+//! disconnects the other, dropping whatever the ring still holds.
+
+#![cfg(all(feature = "std", not(parking_futex = "custom"), not(parking_single_threaded)))]
+
+mod common;
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+use parking::mpsc::{RecvError, RecvTimeoutError, TryRecvError, TrySendError};
+use parking::spsc;
+
+use common::TIMEOUT;
+
+#[test]
+fn elements_arrive_in_order_through_a_small_ring() {
+    let (mut producer, mut consumer) = spsc::ring(4);
+    let t = thread::spawn(move || {
+        for i in 0..10_000 {
+            producer.push(i).unwrap();
+        }
+    });
+    for i in 0..10_000 {
+        assert_eq!(consumer.pop(), Ok(i));
+    }
+    assert_eq!(consumer.pop(), Err(RecvError));
+    t.join().unwrap();
+}
+
+#[test]
+fn pop_parks_until_an_element_is_pushed() {
+    let (mut producer, mut consumer) = spsc::ring(1);
+    let t = thread::spawn(move || {
+        thread::sleep(TIMEOUT);
+        producer.push(7).unwrap();
+        producer
+    });
+    let start = Instant::now();
+    assert_eq!(consumer.pop(), Ok(7));
+    assert!(start.elapsed() >= TIMEOUT);
+    drop(t.join().unwrap());
+}
+
+#[test]
+fn pop_timeout_times_out_on_an_empty_ring() {
+    let (_producer, mut consumer) = spsc::ring::<()>(1);
+    let start = Instant::now();
+    assert_eq!(consumer.pop_timeout(TIMEOUT), Err(RecvTimeoutError::Timeout));
+    assert!(start.elapsed() >= TIMEOUT);
+}
+
+#[test]
+fn push_parks_while_the_ring_is_full() {
+    let (mut producer, mut consumer) = spsc::ring(1);
+    producer.push(1).unwrap();
+    assert_eq!(producer.try_push(2), Err(TrySendError::Full(2)));
+
+    let t = thread::spawn(move || {
+        let start = Instant::now();
+        producer.push(2).unwrap();
+        start.elapsed()
+    });
+    thread::sleep(TIMEOUT);
+    assert_eq!(consumer.pop(), Ok(1));
+    assert!(t.join().unwrap() >= TIMEOUT);
+    assert_eq!(consumer.pop(), Ok(2));
+}
+
+#[test]
+fn dropping_the_producer_disconnects_after_draining() {
+    let (mut producer, mut consumer) = spsc::ring(2);
+    producer.push(1).unwrap();
+    drop(producer);
+    assert_eq!(consumer.try_pop(), Ok(1));
+    assert_eq!(consumer.try_pop(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn dropping_the_producer_wakes_a_parked_consumer() {
+    let (producer, mut consumer) = spsc::ring::<()>(1);
+    let t = thread::spawn(move || {
+        thread::sleep(TIMEOUT);
+        drop(producer);
+    });
+    assert_eq!(consumer.pop(), Err(RecvError));
+    t.join().unwrap();
+}
+
+#[test]
+fn dropping_the_consumer_wakes_a_parked_producer() {
+    let (mut producer, consumer) = spsc::ring(1);
+    producer.push(1).unwrap();
+    let t = thread::spawn(move || producer.push(2));
+    thread::sleep(TIMEOUT);
+    drop(consumer);
+    assert_eq!(t.join().unwrap().unwrap_err().0, 2);
+}
+
+#[test]
+fn elements_left_in_the_ring_are_dropped() {
+    let element = Arc::new(());
+    let (mut producer, consumer) = spsc::ring(4);
+    for _ in 0..3 {
+        producer.push(element.clone()).unwrap();
+    }
+    drop(consumer);
+    drop(producer);
+    assert_eq!(Arc::strong_count(&element), 1);
+}
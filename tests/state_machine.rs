@@ -0,0 +1,123 @@
+//! Random sequences of park/unpark operations checked against a model of the notification.
+//!
+//! A parker holds at most one notification. An unpark that finds none pending stores one and
+//! reports `true`, and a park consumes it. A state word outside EMPTY/PARKED/NOTIFIED panics
+//! inside the backends, so every case running to completion also checks that invariant.
+
+#![cfg(all(feature = "std", not(parking_futex = "custom")))]
+
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use proptest::prelude::*;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Unpark,
+    /// Park with a timeout of this many microseconds
+    ParkTimeout(u64),
+    /// Park forever, only generated where a notification is known to be pending or coming
+    Park
+}
+
+fn timeout() -> impl Strategy<Value = u64> {
+    prop_oneof![Just(0), 1..200u64]
+}
+
+/// Operations on one thread, which never parks forever without a notification pending
+fn sequential() -> impl Strategy<Value = Vec<Op>> {
+    prop::collection::vec(prop_oneof![
+        3 => Just(Op::Unpark),
+        2 => timeout().prop_map(Op::ParkTimeout),
+        1 => Just(Op::Park)
+    ], 0..64)
+}
+
+/// Timed and untimed parks for the parking thread
+fn parks() -> impl Strategy<Value = Vec<Op>> {
+    prop::collection::vec(prop_oneof![
+        3 => timeout().prop_map(Op::ParkTimeout),
+        1 => Just(Op::Park)
+    ], 0..32)
+}
+
+proptest! {
+    #[test]
+    fn matches_model_on_one_thread(ops in sequential()) {
+        let (p, u) = parking::pair();
+        let mut pending = false;
+
+        for op in ops {
+            match op {
+                Op::Unpark => {
+                    prop_assert_eq!(u.unpark(), !pending);
+                    pending = true;
+                }
+                Op::ParkTimeout(micros) => {
+                    prop_assert_eq!(p.park_timeout(Duration::from_micros(micros)), pending);
+                    pending = false;
+                }
+                // Parking forever with nothing pending would hang, so the model consumes it as
+                // a zero timeout instead
+                Op::Park if pending => {
+                    p.park();
+                    pending = false;
+                }
+                Op::Park => prop_assert!(!p.park_timeout(Duration::ZERO))
+            }
+        }
+
+        prop_assert_eq!(p.park_timeout(Duration::ZERO), pending);
+    }
+
+    #[test]
+    fn no_lost_or_invented_notifications(parks in parks(), unparks in prop::collection::vec(0..16usize, 1..4)) {
+        let (p, u) = parking::pair();
+        let done = Arc::new(AtomicBool::new(false));
+
+        // Each producer unparks its share of times, counting the unparks that stored a
+        // notification
+        let producers: Vec<_> = unparks.iter().map(|&n| {
+            let u = u.clone();
+            thread::spawn(move || (0..n).filter(|_| { thread::yield_now(); u.unpark() }).count())
+        }).collect();
+
+        // Untimed parks may outnumber the producers' notifications, so keep unparking until the
+        // parking thread is done
+        let rescuer = {
+            let (u, done) = (u.clone(), done.clone());
+            thread::spawn(move || {
+                let mut stored = 0;
+                while !done.load(SeqCst) {
+                    if u.unpark() {
+                        stored += 1;
+                    }
+                    thread::sleep(Duration::from_millis(1));
+                }
+                stored
+            })
+        };
+
+        let mut consumed = 0;
+        for op in parks {
+            match op {
+                Op::ParkTimeout(micros) => consumed += p.park_timeout(Duration::from_micros(micros)) as usize,
+                Op::Park => {
+                    p.park();
+                    consumed += 1;
+                }
+                Op::Unpark => unreachable!()
+            }
+        }
+        done.store(true, SeqCst);
+
+        let stored = producers.into_iter().map(|t| t.join().unwrap()).sum::<usize>() + rescuer.join().unwrap();
+        let left = p.park_timeout(Duration::ZERO) as usize;
+
+        // Every stored notification is consumed exactly once, and nothing else wakes a park
+        prop_assert_eq!(consumed + left, stored);
+    }
+}
@@ -0,0 +1,92 @@
+//! Timed parks against the clock, on whichever backend the target selects.
+//!
+//! Backends differ in how they take a timeout, absolute on some clock or relative and recomputed
+//! on every wait, so these pin down what every one of them must give: never returning before
+//! the deadline without a notification, returning soon after it, and ending early when
+//! unparked. Upper bounds are loose, as a loaded machine can hold up any wakeup.
+
+#![cfg(all(feature = "std", not(parking_futex = "custom"), not(parking_single_threaded)))]
+
+mod common;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parking::Parker;
+
+use common::{SLACK, TIMEOUT};
+
+#[test]
+fn times_out_no_earlier_than_the_deadline() {
+    let parker = Parker::new();
+    for _ in 0..5 {
+        let start = Instant::now();
+        assert!(!parker.park_timeout(TIMEOUT));
+        let elapsed = start.elapsed();
+        assert!(elapsed >= TIMEOUT, "timed out after {:?}", elapsed);
+        assert!(elapsed < TIMEOUT + SLACK, "timed out after {:?}", elapsed);
+    }
+}
+
+#[test]
+fn deadline_in_the_past_returns_at_once() {
+    let parker = Parker::new();
+    let start = Instant::now();
+    assert!(!parker.park_deadline(start));
+    assert!(!parker.park_timeout(Duration::from_millis(0)));
+    assert!(start.elapsed() < SLACK);
+}
+
+#[test]
+fn unpark_ends_a_timed_park_early() {
+    let parker = Parker::new();
+    let unparker = parker.unparker();
+    let start = Instant::now();
+    let t = thread::spawn(move || {
+        thread::sleep(TIMEOUT);
+        unparker.unpark();
+    });
+    assert!(parker.park_timeout(Duration::from_secs(60)));
+    assert!(start.elapsed() < SLACK);
+    t.join().unwrap();
+}
+
+#[test]
+fn pending_notification_is_consumed_before_timing_out() {
+    let parker = Parker::new();
+    parker.unpark();
+    assert!(parker.park_timeout(TIMEOUT));
+    assert!(!parker.park_timeout(Duration::from_millis(1)));
+}
+
+#[test]
+fn sub_microsecond_timeouts_return_promptly() {
+    let parker = Parker::new();
+    for &timeout in &[Duration::ZERO, Duration::from_nanos(1), Duration::from_nanos(999)] {
+        let start = Instant::now();
+        assert!(!parker.park_timeout(timeout), "notified with a timeout of {:?}", timeout);
+        assert!(start.elapsed() < SLACK, "timed out after {:?}", start.elapsed());
+    }
+}
+
+#[test]
+fn sub_microsecond_timeouts_consume_a_pending_notification() {
+    let parker = Parker::new();
+    for &timeout in &[Duration::ZERO, Duration::from_nanos(1), Duration::from_nanos(999)] {
+        parker.unpark();
+        assert!(parker.park_timeout(timeout), "timed out with a timeout of {:?}", timeout);
+        assert!(!parker.park_timeout(timeout));
+    }
+}
+
+#[test]
+fn unrepresentable_timeout_waits_for_unpark() {
+    let parker = Parker::new();
+    let unparker = parker.unparker();
+    let t = thread::spawn(move || {
+        thread::sleep(TIMEOUT);
+        unparker.unpark();
+    });
+    assert!(parker.park_timeout(Duration::MAX));
+    t.join().unwrap();
+}
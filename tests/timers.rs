@@ -0,0 +1,41 @@
+//! `Timers` under churn: cancelled timers don't pile up in the heap, and the ones left still
+//! fire.
+
+#![cfg(all(feature = "std", not(parking_futex = "custom"), not(parking_single_threaded)))]
+
+use std::time::{Duration, Instant};
+
+use parking::timers::Timers;
+use parking::Parker;
+
+#[test]
+fn cancelled_timers_do_not_pile_up_in_the_heap() {
+    let timers = Timers::new();
+    let parker = Parker::new();
+    let far = Instant::now() + Duration::from_secs(3600);
+    let kept = timers.insert(far, parker.unparker());
+    for i in 0..10_000 {
+        let key = timers.insert(far + Duration::from_millis(i), parker.unparker());
+        assert!(timers.cancel(key));
+        assert!(timers.heap_len() <= 2 * timers.len(), "{} in the heap", timers.heap_len());
+    }
+    assert_eq!(timers.len(), 1);
+    assert_eq!(timers.next_deadline(), Some(far));
+    assert!(timers.cancel(kept));
+    assert_eq!(timers.heap_len(), 0);
+}
+
+#[test]
+fn timers_left_after_churn_still_fire() {
+    let timers = Timers::new();
+    let (parker, unparker) = parking::pair();
+    let now = Instant::now();
+    timers.insert(now, unparker.clone());
+    for _ in 0..100 {
+        let key = timers.insert(now, unparker.clone());
+        timers.cancel(key);
+    }
+    assert_eq!(timers.fire_due(), 1);
+    assert!(timers.is_empty());
+    assert!(parker.park_timeout(Duration::ZERO));
+}
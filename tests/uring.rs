@@ -0,0 +1,56 @@
+//! `Parker::uring_wait` gets the bookkeeping of any other park: the observer sees it begin and
+//! end, with or without a notification pending when it starts.
+//!
+//! None of it needs a ring, as the bookkeeping happens in `uring_wait` and `finish`.
+
+#![cfg(all(feature = "io-uring", parking_futex = "linux", target_os = "linux"))]
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use parking::{Observer, Parker};
+
+/// What the observer saw, as the parker and `Some(notified)` for a wakeup or `None` for a park
+struct Recorder(Mutex<Vec<(usize, Option<bool>)>>);
+
+impl Observer for Recorder {
+    fn on_park(&self, parker_id: usize, _deadline: Option<Instant>) {
+        self.0.lock().unwrap().push((parker_id, None));
+    }
+
+    fn on_wakeup(&self, parker_id: usize, notified: bool, _elapsed: Duration) {
+        self.0.lock().unwrap().push((parker_id, Some(notified)));
+    }
+}
+
+static RECORDER: Recorder = Recorder(Mutex::new(Vec::new()));
+
+/// Return what the observer saw of `parker`
+fn seen(parker: &Parker) -> Vec<Option<bool>> {
+    let _ = parking::set_observer(&RECORDER);
+    let events = RECORDER.0.lock().unwrap();
+    events.iter().filter(|(id, _)| *id == parker.id()).map(|(_, event)| *event).collect()
+}
+
+#[test]
+fn observer_sees_a_uring_park() {
+    let parker = Parker::new();
+    assert!(seen(&parker).is_empty());
+    let wait = parker.uring_wait().expect("no notification pending yet");
+    assert_eq!(seen(&parker), [None]);
+    assert!(parker.unpark());
+    assert!(wait.finish());
+    assert_eq!(seen(&parker), [None, Some(true)]);
+
+    drop(parker.uring_wait());
+    assert_eq!(seen(&parker), [None, Some(true), None, Some(false)]);
+}
+
+#[test]
+fn observer_sees_a_uring_park_that_consumes_a_pending_notification() {
+    let parker = Parker::new();
+    assert!(seen(&parker).is_empty());
+    assert!(parker.unpark());
+    assert!(parker.uring_wait().is_none());
+    assert_eq!(seen(&parker), [None, Some(true)]);
+}